@@ -0,0 +1,74 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use crate::{
+    account::friends_views::{friend_requests, friends},
+    api::{
+        client::accounts::{self, UserIdRequestData},
+        ApiResponse,
+    },
+    channel_views::channel_content,
+};
+
+lazy_static! {
+    static ref BLOCKED_USERS: Mutex<HashSet<i64>> = Mutex::new(HashSet::new());
+}
+
+pub fn load() {
+    accounts::get_blocked_users().send(move |r| match r {
+        ApiResponse::Ok(r) => {
+            *BLOCKED_USERS.lock().unwrap() = r.blocked.into_iter().collect();
+            channel_content::refresh();
+        }
+        ApiResponse::BadRequest(_) => log::error!("Failed to load blocked users."),
+    });
+}
+
+pub fn clear() {
+    BLOCKED_USERS.lock().unwrap().clear();
+}
+
+pub fn is_blocked(user_id: i64) -> bool {
+    BLOCKED_USERS.lock().unwrap().contains(&user_id)
+}
+
+pub fn blocked() -> Vec<i64> {
+    let mut blocked = BLOCKED_USERS
+        .lock()
+        .unwrap()
+        .iter()
+        .copied()
+        .collect::<Vec<i64>>();
+    blocked.sort();
+    blocked
+}
+
+/// Blocks the user, which also removes them from friends on the server.
+pub fn block<F>(user_id: i64, callback: F)
+where
+    F: FnOnce() + 'static,
+{
+    accounts::block_user(&UserIdRequestData { user_id }).send(move |r| match r {
+        ApiResponse::Ok(_) => {
+            BLOCKED_USERS.lock().unwrap().insert(user_id);
+            friend_requests::update(|requests| requests.sent.retain(|x| *x != user_id));
+            friends::reload();
+            channel_content::refresh();
+            callback();
+        }
+        ApiResponse::BadRequest(_) => log::error!("Failed to block user."),
+    });
+}
+
+pub fn unblock<F>(user_id: i64, callback: F)
+where
+    F: FnOnce() + 'static,
+{
+    accounts::unblock_user(&UserIdRequestData { user_id }).send(move |r| match r {
+        ApiResponse::Ok(_) => {
+            BLOCKED_USERS.lock().unwrap().remove(&user_id);
+            channel_content::refresh();
+            callback();
+        }
+        ApiResponse::BadRequest(_) => log::error!("Failed to unblock user."),
+    });
+}
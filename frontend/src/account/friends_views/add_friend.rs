@@ -1,9 +1,10 @@
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 use yew::prelude::*;
 
 use crate::{
-    api::{self, ApiResponse, Platform},
+    api::{
+        client::accounts::{self, FriendRequestsSendRequestData},
+        ApiResponse, Platform,
+    },
     helpers::prelude::*,
     localization,
 };
@@ -17,12 +18,6 @@ pub enum Msg {
     Send,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FriendRequestsSendResponseData {
-    receiver_user_id: i64,
-}
-
 impl Component for AddFriend {
     type Message = Msg;
     type Properties = ();
@@ -65,20 +60,17 @@ impl AddFriend {
         let username = Input::by_id("friendRequestUsername").value();
         let status = ctx.link().callback(Msg::SetStatus);
 
-        api::put("accounts/friendrequests")
-            .body(&json!({
-                "platform": Platform::Native,
-                "userIdentifier": username
-            }))
-            .send(
-                move |r: ApiResponse<FriendRequestsSendResponseData>| match r {
-                    ApiResponse::Ok(_) => {
-                        status.emit(Status::with_ok("viewAccountFriendRequestsSentSuccess"));
-                    }
-                    ApiResponse::BadRequest(err) => {
-                        status.emit(Status::with_err(err));
-                    }
-                },
-            );
+        accounts::send_friend_request(&FriendRequestsSendRequestData {
+            platform: Platform::Native,
+            user_identifier: username,
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(_) => {
+                status.emit(Status::with_ok("viewAccountFriendRequestsSentSuccess"));
+            }
+            ApiResponse::BadRequest(err) => {
+                status.emit(Status::with_err(err));
+            }
+        });
     }
 }
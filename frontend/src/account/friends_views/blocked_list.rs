@@ -0,0 +1,64 @@
+use yew::prelude::*;
+
+use crate::{
+    account::{blocked_users, user_views::UserBadge},
+    localization,
+};
+
+pub struct BlockedList {
+    blocked: Vec<i64>,
+}
+
+pub enum Msg {
+    Reload,
+    Unblock(i64),
+}
+
+impl Component for BlockedList {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: &Context<Self>) -> Self {
+        Self {
+            blocked: blocked_users::blocked(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Reload => self.blocked = blocked_users::blocked(),
+            Msg::Unblock(user_id) => {
+                let callback = ctx.link().callback(|_: ()| Msg::Reload);
+                blocked_users::unblock(user_id, move || callback.emit(()));
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        if self.blocked.is_empty() {
+            return html! {
+                <h2>{lang.get("viewAccountFriendsBlockedEmpty")}</h2>
+            };
+        }
+
+        self.blocked
+            .iter()
+            .map(|user_id| {
+                let user_id = *user_id;
+                html! {
+                    <div class="user-profile-container friends-profile-container">
+                        <UserBadge {user_id} with_profile={true} />
+
+                        <button onclick={ctx.link().callback(move |_| Msg::Unblock(user_id))}>{
+                            lang.get("viewAccountFriendsUnblock")
+                        }</button>
+                    </div>
+                }
+            })
+            .collect::<Html>()
+    }
+}
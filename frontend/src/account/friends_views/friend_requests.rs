@@ -1,29 +1,94 @@
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use std::collections::HashMap;
+
 use yew::prelude::*;
 
 use crate::{
-    account::load_user::{LoadUser, LoadUserContext},
-    api::{self, ApiResponse},
+    account::{blocked_users, friends_views::friends, user_views::UserBadge},
+    api::{
+        client::accounts::{
+            self, FriendRequestsLoadResponseData, FriendRequestsRespondRequestData,
+        },
+        ApiResponse, ErrorDataElement,
+    },
+    helpers::prelude::*,
     localization,
+    store::{self, friend_requests::PendingFriendRequests},
 };
 
+pub fn load() {
+    accounts::get_friend_requests().send(move |r| match r {
+        ApiResponse::Ok(r) => update(|data| *data = r),
+        ApiResponse::BadRequest(_) => log::error!("Failed to load friend requests."),
+    });
+}
+
+pub fn clear() {
+    store::reset::<PendingFriendRequests>();
+}
+
+pub fn received_count() -> usize {
+    match &store::get::<PendingFriendRequests>().requests {
+        Some(data) => data.received.len(),
+        None => 0,
+    }
+}
+
+/// Returns whether the friend request was sent to the user and whether it was received from them.
+pub fn relation(user_id: i64) -> (bool, bool) {
+    match &store::get::<PendingFriendRequests>().requests {
+        Some(data) => (
+            data.sent.contains(&user_id),
+            data.received.contains(&user_id),
+        ),
+        None => (false, false),
+    }
+}
+
+/// Accepts the friend request. Request might be already cancelled by the other user, so requests
+/// are loaded again when it fails.
+pub fn accept<F>(requested_friend_user_id: i64, error: F)
+where
+    F: FnOnce(HashMap<String, ErrorDataElement>) + 'static,
+{
+    accounts::accept_friend_request(&FriendRequestsRespondRequestData {
+        requested_friend_user_id,
+    })
+    .send(move |r| match r {
+        ApiResponse::Ok(_) => {
+            update(|data| data.received.retain(|x| *x != requested_friend_user_id));
+            friends::reload();
+        }
+        ApiResponse::BadRequest(err) => {
+            error(err);
+            load();
+        }
+    });
+}
+
+/// Modifies cached requests and refreshes views which display them.
+pub(crate) fn update<F>(f: F)
+where
+    F: FnOnce(&mut FriendRequestsLoadResponseData),
+{
+    store::dispatch(|state: &mut PendingFriendRequests| {
+        let data = state.requests.get_or_insert_with(Default::default);
+        f(data);
+
+        // Requests from blocked users are suppressed.
+        data.received.retain(|x| !blocked_users::is_blocked(*x));
+    });
+}
+
 pub struct FriendRequests {
-    data: Option<FriendRequestsLoadResponseData>,
+    status: Html,
+    _subscription: store::Subscription,
 }
 
 pub enum Msg {
-    Reload,
-    Load(FriendRequestsLoadResponseData),
+    Refresh,
     Reject(i64),
     Accept(i64),
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FriendRequestsLoadResponseData {
-    received: Vec<i64>,
-    sent: Vec<i64>,
+    SetStatus(Html),
 }
 
 impl Component for FriendRequests {
@@ -31,23 +96,30 @@ impl Component for FriendRequests {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let s = Self { data: None };
-        s.load(ctx);
-        s
+        load();
+        Self {
+            status: Default::default(),
+            _subscription: store::subscribe::<PendingFriendRequests>(
+                ctx.link().callback(|_| Msg::Refresh),
+            ),
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            Msg::Reload => self.load(ctx),
-            Msg::Load(data) => self.data = Some(data),
+            Msg::Refresh => (),
             Msg::Reject(requested_friend_user_id) => {
                 self.reject(ctx, requested_friend_user_id);
                 return false;
             }
             Msg::Accept(requested_friend_user_id) => {
-                self.accept(ctx, requested_friend_user_id);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accept(requested_friend_user_id, move |err| {
+                    status.emit(Status::with_err(err))
+                });
                 return false;
             }
+            Msg::SetStatus(status) => self.status = status,
         };
         true
     }
@@ -55,7 +127,8 @@ impl Component for FriendRequests {
     fn view(&self, ctx: &Context<Self>) -> Html {
         let lang = localization::get_language();
 
-        let data = match &self.data {
+        let state = store::get::<PendingFriendRequests>();
+        let data = match &state.requests {
             Some(data) => {
                 let mut vec = Vec::new();
 
@@ -65,13 +138,7 @@ impl Component for FriendRequests {
                         let user_id = *e;
                         vec.push(html! {
                             <div class="friends-profile-container">
-                                <LoadUser<()>
-                                    props={()}
-                                    user_id={user_id}
-                                    view={Callback::from(process_user_view)}
-                                    with_status={false}
-                                    refresh={false}
-                                />
+                                <UserBadge user_id={user_id} with_profile={true} />
 
                                 <div>
                                     <button onclick={ctx.link().callback(move |_| Msg::Accept(user_id))}>{
@@ -92,13 +159,7 @@ impl Component for FriendRequests {
                         let user_id = *e;
                         vec.push(html! {
                             <div class="user-profile-container friends-profile-container">
-                                <LoadUser<()>
-                                    props={()}
-                                    user_id={user_id}
-                                    view={Callback::from(process_user_view)}
-                                    with_status={false}
-                                    refresh={false}
-                                />
+                                <UserBadge user_id={user_id} with_profile={true} />
 
                                 <button onclick={ctx.link().callback(move |_| Msg::Reject(user_id))}>{
                                     lang.get("viewAccountFriendRequestsCancel")
@@ -120,64 +181,27 @@ impl Component for FriendRequests {
         };
 
         html! { <>
+            {self.status.clone()}
             {data}
         </> }
     }
 }
 
 impl FriendRequests {
-    fn load(&self, ctx: &Context<Self>) {
-        let callback = ctx.link().callback(Msg::Load);
-
-        api::get("accounts/friendrequests").send(
-            move |r: ApiResponse<FriendRequestsLoadResponseData>| match r {
-                ApiResponse::Ok(r) => callback.emit(r),
-                ApiResponse::BadRequest(_) => todo!(),
-            },
-        );
-    }
-
     fn reject(&self, ctx: &Context<Self>, requested_friend_user_id: i64) {
-        let callback = ctx.link().callback(|_: ()| Msg::Reload);
-
-        api::delete("accounts/friendrequests")
-            .body(&json!({
-                "requestedFriendUserId": requested_friend_user_id
-            }))
-            .send_without_ok(move |r| match r {
-                ApiResponse::Ok(_) => callback.emit(()),
-                ApiResponse::BadRequest(_) => todo!(),
-            });
-    }
-
-    fn accept(&self, ctx: &Context<Self>, requested_friend_user_id: i64) {
-        let callback = ctx.link().callback(|_: ()| Msg::Reload);
-
-        api::post("accounts/friendrequests")
-            .body(&json!({
-                "requestedFriendUserId": requested_friend_user_id
-            }))
-            .send_without_ok(move |r| match r {
-                ApiResponse::Ok(_) => callback.emit(()),
-                ApiResponse::BadRequest(_) => todo!(),
-            });
-    }
-}
-
-fn process_user_view(ctx: LoadUserContext<()>) -> Html {
-    if ctx.user.is_none() {
-        return html! { {"Loading..."} };
-    }
-    let user = ctx.user.unwrap();
-
-    html! {
-        <div class="user-profile">
-            <img class="user-avatar" src={user.avatar_url.clone()} alt={"avatar"} />
-            <div class="select">
-                <label class="user-name">{user.name.clone()}</label>
-                <br/>
-                <span class="user-info">{"@"}{user.username.clone()}</span>
-            </div>
-        </div>
+        let status = ctx.link().callback(Msg::SetStatus);
+        accounts::reject_friend_request(&FriendRequestsRespondRequestData {
+            requested_friend_user_id,
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(_) => update(|data| {
+                data.received.retain(|x| *x != requested_friend_user_id);
+                data.sent.retain(|x| *x != requested_friend_user_id);
+            }),
+            ApiResponse::BadRequest(err) => {
+                status.emit(Status::with_err(err));
+                load();
+            }
+        });
     }
 }
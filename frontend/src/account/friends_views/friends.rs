@@ -1,29 +1,75 @@
+use std::{rc::Rc, sync::Arc};
+
 use yew::prelude::*;
 
 use crate::{
-    account::friends_views::{
-        add_friend::AddFriend, friend_requests::FriendRequests, friends_list::FriendsList,
+    account::{
+        friends_views::{
+            add_friend::AddFriend,
+            blocked_list::BlockedList,
+            friend_requests::{self, FriendRequests},
+            friends_list::FriendsList,
+        },
+        load_user::{self, User},
+    },
+    api::{
+        client::accounts::{self, FriendRemoveRequestData, FriendsLoadResponseData},
+        query::{self, QueryOptions},
+        ApiResponse,
     },
-    app, localization,
-    route::{self, Route},
+    localization,
+    presence::STATUS_OFFLINE,
+    route::{self, FriendsTab, Route},
+    store::{self, friend_requests::PendingFriendRequests, users::Users},
 };
 
-pub struct Friends {
-    props: Props,
-    state: Msg,
+const FRIENDS_PATH: &str = "accounts/friends";
+const FRIENDS_QUERY: QueryOptions = QueryOptions {
+    fresh_for: 30000.0,
+    stale_for: 600000.0,
+};
+
+/// Ids of friends of the current user, cached between the views which need them.
+pub async fn load_friend_ids() -> Option<Arc<FriendsLoadResponseData>> {
+    query::fetch(accounts::get_friends(), FRIENDS_QUERY).await
 }
 
-#[derive(PartialEq)]
-pub enum Msg {
-    Online,
-    All,
-    Pending,
-    Add,
+/// Friends changed, the list is loaded again from the API.
+pub fn reload() {
+    query::invalidate(FRIENDS_PATH);
+}
+
+pub fn remove_friend<F>(friend_user_id: i64, callback: F)
+where
+    F: FnOnce() + 'static,
+{
+    accounts::remove_friend(&FriendRemoveRequestData { friend_user_id }).send(move |r| match r {
+        ApiResponse::Ok(_) => {
+            reload();
+            callback();
+        }
+        ApiResponse::BadRequest(_) => log::error!("Failed to remove friend."),
+    });
+}
+
+pub struct Friends {
+    friends: Option<Vec<Arc<User>>>,
+    _subscription: query::Subscription,
+    _users_subscription: store::Subscription,
+    _requests_subscription: store::Subscription,
 }
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
-    pub app_callback: Callback<app::Msg>,
+    pub tab: FriendsTab,
+}
+
+pub enum Msg {
+    Select(FriendsTab),
+    Refresh,
+    Reload,
+    Load(Vec<Arc<User>>),
+    UsersChanged(Rc<Users>),
 }
 
 impl Component for Friends {
@@ -31,53 +77,114 @@ impl Component for Friends {
     type Properties = Props;
 
     fn create(ctx: &Context<Self>) -> Self {
-        Self {
-            props: ctx.props().clone(),
-            state: Msg::Online,
-        }
+        let s = Self {
+            friends: None,
+            _subscription: query::subscribe(FRIENDS_PATH, ctx.link().callback(|_| Msg::Reload)),
+            _users_subscription: store::subscribe(ctx.link().callback(Msg::UsersChanged)),
+            _requests_subscription: store::subscribe::<PendingFriendRequests>(
+                ctx.link().callback(|_| Msg::Refresh),
+            ),
+        };
+        s.load(ctx);
+        s
     }
 
-    fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
-        match self.state == msg {
-            true => return false,
-            false => self.state = msg,
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Select(tab) => {
+                if ctx.props().tab != tab {
+                    route::navigate(Route::friends(tab));
+                }
+                return false;
+            }
+            Msg::Refresh => (),
+            Msg::Reload => {
+                self.load(ctx);
+                return false;
+            }
+            Msg::Load(friends) => self.friends = Some(friends),
+            Msg::UsersChanged(users) => {
+                let mut changed = false;
+                for friend in self.friends.iter_mut().flatten() {
+                    match users.get(friend.user_id, false) {
+                        Some(user) if !Arc::ptr_eq(friend, &user) => {
+                            *friend = user;
+                            changed = true;
+                        }
+                        _ => (),
+                    }
+                }
+                return changed;
+            }
         };
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let lang = localization::get_language();
+        let tab = ctx.props().tab;
 
-        let app_callback = self.props.app_callback.clone();
-        let content = match self.state {
-            Msg::Online => html! { <FriendsList {app_callback} /> },
-            Msg::All => html! { <FriendsList {app_callback} /> },
-            Msg::Pending => html! { <FriendRequests /> },
-            Msg::Add => html! { <AddFriend /> },
+        let content = match tab {
+            FriendsTab::Online | FriendsTab::All => match &self.friends {
+                Some(friends) => {
+                    let friends = friends
+                        .iter()
+                        .filter(|x| tab == FriendsTab::All || is_online(x))
+                        .cloned()
+                        .collect::<Vec<Arc<User>>>();
+                    html! {
+                        <FriendsList
+                            friends={Rc::new(friends)}
+                            reload={ctx.link().callback(|_| Msg::Reload)}
+                        />
+                    }
+                }
+                None => html! { <p>{"Loading..."}</p> },
+            },
+            FriendsTab::Pending => html! { <FriendRequests /> },
+            FriendsTab::Blocked => html! { <BlockedList /> },
+            FriendsTab::Add => html! { <AddFriend /> },
         };
 
-        let add_friend_class = match self.state == Msg::Add {
+        let add_friend_class = match tab == FriendsTab::Add {
             true => "add-friend-selected",
             false => "add-friend",
         };
 
+        let (online_count, all_count) = match &self.friends {
+            Some(friends) => (
+                format!(" ({})", friends.iter().filter(|x| is_online(x)).count()),
+                format!(" ({})", friends.len()),
+            ),
+            None => Default::default(),
+        };
+
+        let pending_badge = match friend_requests::received_count() {
+            0 => html! {},
+            count => html! { <span class="friends-pending-badge">{count}</span> },
+        };
+
         html! {
             <div class="noselect">
-                <route::Router route={Route::Friends} />
-
                 <header class="friends-header">
                     <h1>{lang.get("viewAccountFriendsTitle")}</h1>
                     <button
-                        onclick={ctx.link().callback(|_| Msg::Online)} class={self.get_selected(Msg::Online)}
-                    >{lang.get("viewAccountFriendsOnline")}</button>
+                        onclick={ctx.link().callback(|_| Msg::Select(FriendsTab::Online))} class={Self::get_selected(ctx, FriendsTab::Online)}
+                    >{lang.get("viewAccountFriendsOnline")}{online_count}</button>
+                    <button
+                        onclick={ctx.link().callback(|_| Msg::Select(FriendsTab::All))} class={Self::get_selected(ctx, FriendsTab::All)}
+                    >{lang.get("viewAccountFriendsAll")}{all_count}</button>
                     <button
-                        onclick={ctx.link().callback(|_| Msg::All)} class={self.get_selected(Msg::All)}
-                    >{lang.get("viewAccountFriendsAll")}</button>
+                        onclick={ctx.link().callback(|_| Msg::Select(FriendsTab::Pending))} class={Self::get_selected(ctx, FriendsTab::Pending)}
+                    >
+                        {lang.get("viewAccountFriendsPending")}
+                        {pending_badge}
+                    </button>
                     <button
-                        onclick={ctx.link().callback(|_| Msg::Pending)} class={self.get_selected(Msg::Pending)}
-                    >{lang.get("viewAccountFriendsPending")}</button>
+                        onclick={ctx.link().callback(|_| Msg::Select(FriendsTab::Blocked))} class={Self::get_selected(ctx, FriendsTab::Blocked)}
+                    >{lang.get("viewAccountFriendsBlocked")}</button>
                     <button
-                        onclick={ctx.link().callback(|_| Msg::Add)} class={add_friend_class}
+                        onclick={ctx.link().callback(|_| Msg::Select(FriendsTab::Add))} class={add_friend_class}
                     >{lang.get("viewAccountFriendsAdd")}</button>
                 </header>
 
@@ -88,11 +195,25 @@ impl Component for Friends {
 }
 
 impl Friends {
-    fn get_selected(&self, expected: Msg) -> String {
-        match self.state == expected {
+    fn load(&self, ctx: &Context<Self>) {
+        let callback = ctx.link().callback(Msg::Load);
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(data) = load_friend_ids().await {
+                callback.emit(load_user::get_users(&data.friends, true).await);
+            }
+        });
+    }
+
+    fn get_selected(ctx: &Context<Self>, expected: FriendsTab) -> String {
+        match ctx.props().tab == expected {
             true => "button-selected",
             false => "",
         }
         .to_owned()
     }
 }
+
+fn is_online(user: &User) -> bool {
+    matches!(&user.status, Some(status) if status.status != STATUS_OFFLINE)
+}
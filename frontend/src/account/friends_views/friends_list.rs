@@ -1,39 +1,47 @@
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use std::{rc::Rc, sync::Arc};
+
 use yew::prelude::*;
 
 use crate::{
-    account::load_user::{LoadUser, LoadUserContext},
-    api::{self, ApiResponse},
-    app, localization,
+    account::{
+        blocked_users,
+        friends_views::friends,
+        load_user::User,
+        user_views::{UserAvatar, UserName},
+    },
+    direct_messages_views::direct_channels,
+    helpers::prelude::*,
+    localization, presence,
 };
 
 pub struct FriendsList {
     props: Props,
-    data: Option<FriendsListLoadResponseData>,
+    search: String,
+    sort: Sort,
+    context_menu: Option<i64>,
+    status: Html,
 }
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
-    pub app_callback: Callback<app::Msg>,
+    pub friends: Rc<Vec<Arc<User>>>,
+    pub reload: Callback<()>,
 }
 
-pub enum Msg {
-    Load(FriendsListLoadResponseData),
-    Reload,
-    OpenChannel(i64),
+#[derive(PartialEq, Clone, Copy)]
+pub enum Sort {
+    Name,
+    Status,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FriendsListLoadResponseData {
-    friends: Vec<i64>,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetChannelIdFromUserIdResponseData {
-    channel_id: i64,
+pub enum Msg {
+    Search,
+    Sort(Sort),
+    OpenChannel(i64),
+    ToggleContextMenu(i64),
+    Remove(i64),
+    Block(i64),
+    SetStatus(Html),
 }
 
 impl Component for FriendsList {
@@ -41,25 +49,47 @@ impl Component for FriendsList {
     type Properties = Props;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let s = Self {
+        Self {
             props: ctx.props().clone(),
-            data: None,
-        };
-        s.load(ctx);
-        s
+            search: String::new(),
+            sort: Sort::Name,
+            context_menu: None,
+            status: Default::default(),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.props = ctx.props().clone();
+        true
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            Msg::Reload => {
-                self.load(ctx);
-                return false;
-            }
-            Msg::Load(data) => self.data = Some(data),
+            Msg::Search => self.search = Input::by_id("friendsSearch").value().to_lowercase(),
+            Msg::Sort(sort) => self.sort = sort,
             Msg::OpenChannel(friend_user_id) => {
-                self.open_channel(friend_user_id);
+                let status = ctx.link().callback(Msg::SetStatus);
+                direct_channels::open_with_user(friend_user_id, move |err| {
+                    status.emit(Status::with_err(err))
+                });
                 return false;
             }
+            Msg::ToggleContextMenu(friend_user_id) => {
+                self.context_menu = match self.context_menu == Some(friend_user_id) {
+                    true => None,
+                    false => Some(friend_user_id),
+                }
+            }
+            Msg::Remove(friend_user_id) => {
+                self.context_menu = None;
+                friends::remove_friend(friend_user_id, || ());
+            }
+            Msg::Block(friend_user_id) => {
+                self.context_menu = None;
+                let reload = self.props.reload.clone();
+                blocked_users::block(friend_user_id, move || reload.emit(()));
+            }
+            Msg::SetStatus(status) => self.status = status,
         };
         true
     }
@@ -67,77 +97,114 @@ impl Component for FriendsList {
     fn view(&self, ctx: &Context<Self>) -> Html {
         let lang = localization::get_language();
 
-        let data = match &self.data {
-            Some(data) => {
-                let mut vec = Vec::new();
-
-                for e in &data.friends {
-                    let user_id = *e;
-                    vec.push(html! {
-                        <div class="user-profile-container friends-profile-container">
-                            <LoadUser<()>
-                                props={()}
-                                user_id={user_id}
-                                view={Callback::from(process_user_view)}
-                                with_status={false}
-                                refresh={false}
-                            />
-
-                            <button onclick={ctx.link().callback(move |_| Msg::OpenChannel(user_id))}>{
-                                lang.get("viewAccountFriendsOpenChat")
-                            }</button>
+        let mut friends = self
+            .props
+            .friends
+            .iter()
+            .filter(|x| {
+                self.search.is_empty()
+                    || x.name.to_lowercase().contains(&self.search)
+                    || x.username.to_lowercase().contains(&self.search)
+            })
+            .collect::<Vec<&Arc<User>>>();
+        match self.sort {
+            Sort::Name => friends.sort_by_key(|x| x.name.to_lowercase()),
+            Sort::Status => friends.sort_by_key(|x| (status_order(x), x.name.to_lowercase())),
+        };
+
+        let data = match friends.is_empty() {
+            true => html! { <p>{lang.get("viewAccountFriendsEmpty")}</p> },
+            false => friends
+                .into_iter()
+                .map(|user| {
+                    let user_id = user.user_id;
+                    let context_menu = match self.context_menu == Some(user_id) {
+                        true => html! {
+                            <div class="friends-context-menu">
+                                <button onclick={ctx.link().callback(move |_| Msg::Remove(user_id))}>{
+                                    lang.get("viewAccountFriendsRemove")
+                                }</button>
+                                <button class="friends-context-menu-danger" onclick={ctx.link().callback(move |_| Msg::Block(user_id))}>{
+                                    lang.get("viewAccountFriendsBlock")
+                                }</button>
+                            </div>
+                        },
+                        false => html! {},
+                    };
+
+                    html! {
+                        <div
+                            class="user-profile-container friends-profile-container"
+                            oncontextmenu={ctx.link().callback(move |e: MouseEvent| {
+                                e.prevent_default();
+                                Msg::ToggleContextMenu(user_id)
+                            })}
+                        >
+                            {user_view(user)}
+
+                            <div class="friends-profile-buttons">
+                                <button onclick={ctx.link().callback(move |_| Msg::OpenChannel(user_id))}>{
+                                    lang.get("viewAccountFriendsOpenChat")
+                                }</button>
+                                <button onclick={ctx.link().callback(move |_| Msg::ToggleContextMenu(user_id))}>{"⋯"}</button>
+                            </div>
+                            {context_menu}
                         </div>
-                    })
-                }
+                    }
+                })
+                .collect::<Html>(),
+        };
 
-                vec
-            }
-            None => vec![html! { <p>{"Loading..."}</p> }],
+        let sort_class = |sort: Sort| match self.sort == sort {
+            true => "button-selected",
+            false => "",
         };
 
         html! { <>
+            <div class="friends-list-toolbar">
+                <input
+                    id="friendsSearch"
+                    type="text"
+                    placeholder={lang.get("viewAccountFriendsSearch")}
+                    oninput={ctx.link().callback(|_| Msg::Search)}
+                />
+                <span>{lang.get("viewAccountFriendsSort")}</span>
+                <button class={sort_class(Sort::Name)} onclick={ctx.link().callback(|_| Msg::Sort(Sort::Name))}>{
+                    lang.get("viewAccountFriendsSortName")
+                }</button>
+                <button class={sort_class(Sort::Status)} onclick={ctx.link().callback(|_| Msg::Sort(Sort::Status))}>{
+                    lang.get("viewAccountFriendsSortStatus")
+                }</button>
+            </div>
+            {self.status.clone()}
             {data}
         </> }
     }
 }
 
-impl FriendsList {
-    fn load(&self, ctx: &Context<Self>) {
-        let callback = ctx.link().callback(Msg::Load);
-
-        api::get("accounts/friends").send(
-            move |r: ApiResponse<FriendsListLoadResponseData>| match r {
-                ApiResponse::Ok(r) => callback.emit(r),
-                ApiResponse::BadRequest(_) => todo!(),
-            },
-        );
-    }
-
-    fn open_channel(&self, friend_user_id: i64) {
-        let callback = self.props.app_callback.clone();
-
-        api::post("channels/direct/getchannelidfromuserid")
-            .body(&json!({ "userId": friend_user_id }))
-            .send(
-                move |r: ApiResponse<GetChannelIdFromUserIdResponseData>| match r {
-                    ApiResponse::Ok(r) => callback.emit(app::Msg::OpennedChannel(r.channel_id)),
-                    ApiResponse::BadRequest(_) => todo!(),
-                },
-            );
+/// Online users go first and offline ones last.
+fn status_order(user: &User) -> i32 {
+    match user.status.as_ref().map(|x| x.status) {
+        Some(presence::STATUS_ONLINE) => 0,
+        Some(presence::STATUS_IDLE) => 1,
+        Some(presence::STATUS_DO_NOT_DISTURB) => 2,
+        _ => 3,
     }
 }
 
-fn process_user_view(ctx: LoadUserContext<()>) -> Html {
-    if ctx.user.is_none() {
-        return html! { {"Loading..."} };
-    }
-    let user = ctx.user.unwrap();
+fn user_view(user: &User) -> Html {
+    let user_id = user.user_id;
+    let status = match &user.status {
+        Some(status) => status.icon_html(),
+        None => html! {},
+    };
 
     html! {
         <div class="user-profile">
-            <img class="user-avatar" src={user.avatar_url.clone()} alt={"avatar"} />
+            <UserAvatar {user_id} with_profile={true} />
+            {status}
             <div class="select user-content">
-                <label class="user-name">{user.name.clone()}</label>
+                <UserName {user_id} class="user-name" with_profile={true} />
                 <br/>
                 <span class="user-info">{"@"}{user.username.clone()}</span>
             </div>
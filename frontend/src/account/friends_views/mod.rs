@@ -1,4 +1,6 @@
 pub mod add_friend;
+pub mod blocked_list;
 pub mod friend_requests;
 pub mod friends;
 pub mod friends_list;
+pub mod notifier_process;
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use super::{friend_requests, friends};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRequestEventData {
+    user_id: i64,
+}
+
+/// Other user sent a friend request to the current user.
+pub fn received_friend_request(data: FriendRequestEventData) {
+    friend_requests::update(|requests| {
+        if !requests.received.contains(&data.user_id) {
+            requests.received.push(data.user_id);
+        }
+    });
+}
+
+/// Other user accepted a friend request sent by the current user.
+pub fn accepted_friend_request(data: FriendRequestEventData) {
+    friend_requests::update(|requests| requests.sent.retain(|x| *x != data.user_id));
+    friends::reload();
+}
+
+/// Other user rejected a friend request sent by the current user.
+pub fn rejected_friend_request(data: FriendRequestEventData) {
+    friend_requests::update(|requests| requests.sent.retain(|x| *x != data.user_id));
+}
+
+/// Other user cancelled a friend request sent to the current user.
+pub fn cancelled_friend_request(data: FriendRequestEventData) {
+    friend_requests::update(|requests| requests.received.retain(|x| *x != data.user_id));
+}
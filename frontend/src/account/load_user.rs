@@ -1,24 +1,30 @@
 use std::{
-    collections::HashMap,
-    marker::PhantomData,
-    num::NonZeroUsize,
-    sync::{atomic::AtomicI64, Arc, Mutex},
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
 };
 
-use lazy_static::__Deref;
-use lru::LruCache;
+use async_std::channel::{self, Sender};
+use gloo_timers::callback::Timeout;
 use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
-use crate::{api, common::UnsafeSync};
+use crate::{
+    api::{
+        client::accounts::{self, UsersQueryData},
+        query::{self, QueryOptions},
+    },
+    store::{
+        self,
+        users::{Users, UsersAction},
+    },
+};
+
+/// Maximal number of users loaded by one request, which keeps the query short enough.
+const MAX_BATCH_SIZE: usize = 100;
 
 lazy_static! {
-    static ref REQUESTING_LOCK: async_std::sync::Mutex<()> = async_std::sync::Mutex::new(());
-    static ref CACHED_USERS: Mutex<LruCache<i64, Arc<User>>> =
-        Mutex::new(LruCache::new(NonZeroUsize::new(2048).unwrap()));
-    static ref NEXT_ID: AtomicI64 = AtomicI64::new(0);
-    static ref DISPLAYED_COMPONENTS: Mutex<HashMap<i64, HashMap<i64, DisplayedComponent>>> =
-        Mutex::new(HashMap::new());
+    /// Users requested in the current tick, by whether they are requested with status.
+    static ref PENDING_BATCHES: Mutex<HashMap<bool, PendingBatch>> = Mutex::new(HashMap::new());
 }
 
 pub fn received_user_status(data: ReceivedUserStatusData) {
@@ -26,29 +32,104 @@ pub fn received_user_status(data: ReceivedUserStatusData) {
 }
 
 pub fn reload_user(user: User) {
-    let user_id = user.user_id;
-    CACHED_USERS.lock().unwrap().put(user_id, Arc::new(user));
-    reload_worker(user_id);
+    store::dispatch(UsersAction::Loaded(vec![user]));
 }
 
 pub fn reload_user_status(user_id: i64, status: UserStatus) {
-    let mut users = CACHED_USERS.lock().unwrap();
-    if let Some(user) = users.get(&user_id) {
-        let mut user = user.deref().clone();
-        user.status = Some(status);
-        users.put(user_id, Arc::new(user));
+    store::dispatch(UsersAction::Status(user_id, status));
+}
+
+pub fn cached_user(user_id: i64) -> Option<Arc<User>> {
+    store::get::<Users>().get(user_id, false)
+}
+
+/// Gets user from the cache, or loads it from the API when it is not cached yet.
+pub async fn get_user(user_id: i64, with_status: bool) -> Option<Arc<User>> {
+    get_users(&[user_id], with_status).await.pop()
+}
+
+/// Gets users from the cache, and loads the missing ones from the API in a single request.
+/// Users which do not exist are omitted.
+pub async fn get_users(user_ids: &[i64], with_status: bool) -> Vec<Arc<User>> {
+    let missing = user_ids
+        .iter()
+        .filter(|x| get_cached(**x, with_status).is_none())
+        .copied()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        load_users(missing, with_status).await;
     }
 
-    reload_worker(user_id);
+    user_ids
+        .iter()
+        .filter_map(|x| get_cached(*x, with_status))
+        .collect()
 }
 
-fn reload_worker(user_id: i64) {
-    let components = DISPLAYED_COMPONENTS.lock().unwrap();
-    if let Some(component_map) = components.get(&user_id) {
-        for component in component_map.values() {
-            component.callback.emit(Msg::Reload(false));
+/// Loads the user again from the API, and updates the components which display them.
+pub fn refresh_user(user_id: i64, with_status: bool) {
+    wasm_bindgen_futures::spawn_local(load_users(vec![user_id], with_status));
+}
+
+fn get_cached(user_id: i64, with_status: bool) -> Option<Arc<User>> {
+    store::get::<Users>().get(user_id, with_status)
+}
+
+/// Loads users from the API and caches them. Users requested in the same tick are loaded
+/// together by one request.
+async fn load_users(user_ids: Vec<i64>, with_status: bool) {
+    let (sender, receiver) = channel::bounded(1);
+    {
+        let mut batches = PENDING_BATCHES.lock().unwrap();
+        if !batches.contains_key(&with_status) {
+            Timeout::new(0, move || {
+                wasm_bindgen_futures::spawn_local(load_batch(with_status));
+            })
+            .forget();
+        }
+
+        let batch = batches.entry(with_status).or_default();
+        batch.user_ids.extend(user_ids);
+        batch.waiters.push(sender);
+    }
+
+    let _ = receiver.recv().await;
+}
+
+async fn load_batch(with_status: bool) {
+    let batch = match PENDING_BATCHES.lock().unwrap().remove(&with_status) {
+        Some(batch) => batch,
+        None => return,
+    };
+
+    let user_ids = batch.user_ids.into_iter().collect::<Vec<_>>();
+    for chunk in user_ids.chunks(MAX_BATCH_SIZE) {
+        let ids = chunk
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        // Same requests in flight are still sent only once.
+        let request = accounts::get_users(&UsersQueryData { ids, with_status });
+        let users = query::refetch(request, QueryOptions::DEDUPLICATE)
+            .await
+            .map_or_else(Vec::new, |data| data.users.clone());
+
+        let missing = chunk
+            .iter()
+            .filter(|x| !users.iter().any(|user| user.user_id == **x))
+            .copied()
+            .collect::<Vec<_>>();
+        store::dispatch(UsersAction::Loaded(users));
+        if !missing.is_empty() {
+            store::dispatch(UsersAction::Missing(missing));
         }
     }
+
+    for waiter in batch.waiters {
+        let _ = waiter.try_send(());
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -58,11 +139,13 @@ pub struct ReceivedUserStatusData {
     status: UserStatus,
 }
 
-struct DisplayedComponent {
-    callback: UnsafeSync<Callback<Msg>>,
+#[derive(Default)]
+struct PendingBatch {
+    user_ids: BTreeSet<i64>,
+    waiters: Vec<Sender<()>>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub user_id: i64,
@@ -72,12 +155,32 @@ pub struct User {
     pub status: Option<UserStatus>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Clone, PartialEq)]
+pub enum UserState {
+    Loading,
+    Loaded(Arc<User>),
+    /// User does not exist, or the request loading them failed.
+    Missing,
+}
+
+impl UserState {
+    pub fn loaded(self) -> Option<Arc<User>> {
+        match self {
+            UserState::Loaded(user) => Some(user),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct UserStatus {
     pub status: i32,
     pub mobile: bool,
     pub rich_presence: String,
+    /// Text chosen by the user, independent of the rich presence.
+    #[serde(default)]
+    pub custom_status: String,
 }
 
 impl UserStatus {
@@ -99,174 +202,26 @@ impl UserStatus {
     }
 }
 
-pub struct LoadUser<T>
-where
-    T: Clone + PartialEq + 'static,
-{
-    id: i64,
-    user: Option<Arc<User>>,
-    phantom: PhantomData<T>,
-}
-
-pub struct LoadUserContext<T>
-where
-    T: Clone + PartialEq + 'static,
-{
-    pub user: Option<Arc<User>>,
-    pub props: T,
-}
-
-pub enum Msg {
-    Reload(bool),
-    Load(Arc<User>),
-}
-
-#[derive(Properties, Clone, PartialEq)]
-pub struct Props<T>
-where
-    T: Clone + PartialEq + 'static,
-{
-    pub props: T,
-    pub user_id: i64,
-    pub view: Callback<LoadUserContext<T>, Html>,
-    pub with_status: bool,
-    pub refresh: bool,
-}
-
-impl<T> Component for LoadUser<T>
-where
-    T: Clone + PartialEq + 'static,
-{
-    type Message = Msg;
-    type Properties = Props<T>;
-
-    fn create(ctx: &Context<Self>) -> Self {
-        let s = Self {
-            id: NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
-            user: None,
-            phantom: PhantomData,
-        };
-        s.changed_worker(ctx, 0);
-        s
-    }
-
-    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
-        self.changed_worker(ctx, old_props.user_id);
-        true
-    }
-
-    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            Msg::Reload(refresh) => {
-                self.load(ctx, refresh);
-                return false;
-            }
-            Msg::Load(user) => self.user = Some(user),
-        };
-        true
-    }
-
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        ctx.props().view.emit(LoadUserContext {
-            user: self.user.clone(),
-            props: ctx.props().props.clone(),
-        })
-    }
-
-    fn destroy(&mut self, ctx: &Context<Self>) {
-        let mut lock = DISPLAYED_COMPONENTS.lock().unwrap();
-        if let Some(set) = lock.get_mut(&ctx.props().user_id) {
-            set.remove(&self.id);
-            if set.is_empty() {
-                lock.remove(&ctx.props().user_id);
-            }
-        }
-    }
-}
-
-impl<T> LoadUser<T>
-where
-    T: Clone + PartialEq + 'static,
-{
-    fn changed_worker(&self, ctx: &Context<Self>, old_id: i64) {
-        {
-            let mut lock = DISPLAYED_COMPONENTS.lock().unwrap();
-            if let Some(set) = lock.get_mut(&old_id) {
-                set.remove(&self.id);
-                if set.is_empty() {
-                    lock.remove(&old_id);
-                }
-            }
-
-            lock.entry(ctx.props().user_id)
-                .or_insert_with(HashMap::new)
-                .insert(
-                    self.id,
-                    DisplayedComponent {
-                        callback: UnsafeSync(ctx.link().callback(|m: Msg| m)),
-                    },
-                );
-        }
-
-        self.load(ctx, ctx.props().refresh);
-    }
-
-    fn load(&self, ctx: &Context<Self>, refresh: bool) {
-        let user_id = ctx.props().user_id;
-        let with_status = ctx.props().with_status;
-
-        let callback = ctx.link().callback(Msg::Load);
-        let mut old_user = None;
-
-        if let Some(user) = CACHED_USERS.lock().unwrap().get(&user_id) {
-            if !refresh {
-                if !with_status || user.status.is_some() == with_status {
-                    callback.emit(user.clone());
-                    return;
-                }
-            } else {
-                old_user = Some(user.clone());
-            }
-        }
-
-        wasm_bindgen_futures::spawn_local(async move {
-            let _lock = REQUESTING_LOCK.lock().await;
-            if let Some(user) = CACHED_USERS.lock().unwrap().get(&user_id) {
-                if !refresh {
-                    if !with_status || user.status.is_some() == with_status {
-                        callback.emit(user.clone());
-                        return;
-                    }
-                } else if let Some(old_user) = old_user {
-                    if !Arc::ptr_eq(user, &old_user) {
-                        callback.emit(user.clone());
-                        return;
-                    }
-                }
-            }
-
-            let endpoint = match with_status {
-                true => "accounts/user/withstatus",
-                false => "accounts/user",
-            };
-
-            let response = api::get(endpoint)
-                .query([("id", user_id.to_string())])
-                .send_async()
-                .await;
-            match response.status() {
-                200 => {
-                    let user = Arc::new(response.json::<User>().await.unwrap());
-                    CACHED_USERS.lock().unwrap().put(user_id, user.clone());
-                    callback.emit(user);
-                }
-                400 => todo!(),
-                _ => unreachable!(),
-            }
-
-            if refresh {
-                reload_worker(user_id);
+/// Gets the user from the cache, or loads it when it is not cached yet. Component is rendered
+/// again when the user changes, or when loading of the user fails.
+#[hook]
+pub fn use_user(user_id: i64, with_status: bool) -> UserState {
+    let user = store::use_selector(
+        move |users: &Users| users.state(user_id, with_status),
+        (user_id, with_status),
+    );
+    use_effect_with_deps(
+        |&(user_id, with_status)| {
+            if get_cached(user_id, with_status).is_none() {
+                wasm_bindgen_futures::spawn_local(load_users(vec![user_id], with_status));
             }
-        });
+        },
+        (user_id, with_status),
+    );
+
+    // Previous user is kept until the new one is selected.
+    match user {
+        UserState::Loaded(user) if user.user_id != user_id => UserState::Loading,
+        user => user,
     }
 }
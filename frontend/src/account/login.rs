@@ -1,28 +1,47 @@
 use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{engine::general_purpose, Engine as _};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use uuid::Uuid;
 use yew::prelude::*;
 use yew_icons::{Icon, IconId};
 
 use crate::{
-    api::{self, ApiResponse},
+    api::{
+        self,
+        client::accounts::{self, LoginRequestData},
+        ApiResponse, ErrorDataElement,
+    },
     app,
-    direct_messages_views::encryption,
+    direct_messages_views::encryption::{self, KeyBackup},
     helpers::prelude::*,
     localization,
-    route::{Route, Router},
 };
 
+// TwoFactorCodeRequired
+const TWO_FACTOR_CODE_REQUIRED: u32 = 1005;
+
 pub struct Login {
     props: Props,
     status: Html,
+    /// Credentials waiting for the second step of the login.
+    two_factor: Option<(String, String)>,
 }
 
 pub enum Msg {
     SetStatus(Html),
     Submit,
+    RequireTwoFactor(String, String),
+    SubmitTwoFactor,
+    CancelTwoFactor,
+}
+
+pub(crate) enum EncryptionSetup {
+    Existing,
+    /// Creates new middle keys, restored from the backup if available.
+    New(Option<KeyBackup>),
+}
+
+pub(crate) enum LoginError {
+    Status(Html),
+    TwoFactorCodeRequired,
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -30,14 +49,6 @@ pub struct Props {
     pub app_callback: Callback<app::Msg>,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct LoginResponseData {
-    user_id: i64,
-    refresh_token: Uuid,
-    message_encryption_salt: i64,
-}
-
 impl Component for Login {
     type Message = Msg;
     type Properties = Props;
@@ -46,6 +57,7 @@ impl Component for Login {
         Self {
             props: ctx.props().clone(),
             status: Status::default_html(),
+            two_factor: None,
         }
     }
 
@@ -56,36 +68,89 @@ impl Component for Login {
                 self.submit(ctx);
                 return false;
             }
+            Msg::RequireTwoFactor(email, password) => {
+                self.two_factor = Some((email, password));
+                self.status = Status::default_html();
+            }
+            Msg::SubmitTwoFactor => {
+                self.submit_two_factor(ctx);
+                return false;
+            }
+            Msg::CancelTwoFactor => {
+                self.two_factor = None;
+                self.status = Status::default_html();
+            }
         };
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let lang = localization::get_language();
+        let app_callback = self.props.app_callback.clone();
+        let display = move |login_view| {
+            let app_callback = app_callback.clone();
+            Callback::from(move |_| app_callback.emit(app::Msg::DisplayLoginView(login_view)))
+        };
+
+        let content = match self.two_factor {
+            Some(_) => html! { <>
+                <h3 id="welcome-text">{lang.get("viewAccountLoginTwoFactorText")}</h3>
+                <input
+                    placeholder={lang.get("viewAccountLoginTwoFactorCode")} name="two-factor-code"
+                    id="two-factor-code" type="text" autocomplete="one-time-code"
+                />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::SubmitTwoFactor)}>
+                    <Icon icon_id={IconId::LucideLogIn}/>
+                    {" "}
+                    {lang.get("viewAccountLoginSubmit")}
+                </button>
+                {self.status.clone()}
+                <p class="login-switch">
+                    <a onclick={ctx.link().callback(|_| Msg::CancelTwoFactor)}>{
+                        lang.get("viewAccountLoginTwoFactorCancel")
+                    }</a>
+                </p>
+            </> },
+            None => html! { <>
+                <h3 id="welcome-text">
+                    <Icon icon_id={IconId::FontAwesomeSolidHandSparkles}/>
+                    {" "}
+                    {lang.get("viewAccountLoginWelcomeText")}
+                </h3>
+                <input placeholder={lang.get("viewAccountLoginEmail")} name="email" id="email" type="email" />
+                <br/>
+                <input placeholder={lang.get("viewAccountLoginPassword")} name="password" id="password" type="password" />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Submit)}>
+                    <Icon icon_id={IconId::LucideLogIn}/>
+                    {" "}
+                    {lang.get("viewAccountLoginSubmit")}
+                </button>
+                {self.status.clone()}
+                <p class="login-switch">
+                    <a onclick={display(app::LoginView::PasswordReset)}>{
+                        lang.get("viewAccountLoginForgotPassword")
+                    }</a>
+                </p>
+                <p class="login-switch">
+                    {lang.get("viewAccountLoginNoAccount")}
+                    {" "}
+                    <a onclick={display(app::LoginView::Registration)}>{
+                        lang.get("viewAccountLoginRegister")
+                    }</a>
+                </p>
+            </> },
+        };
 
         html! {
             <>
-                <Router route={Route::Login} />
                 <link rel="stylesheet" href="/static/css/account/login.css" />
                 <div class="login-container">
                     <div id="login-items-main">
                         <div id="login-items">
                             <h1 id="login-header">{lang.get("viewAccountLoginTitle")}</h1>
-                            <h3 id="welcome-text">
-                                <Icon icon_id={IconId::FontAwesomeSolidHandSparkles}/>
-                                {" "}
-                                {lang.get("viewAccountLoginWelcomeText")}
-                            </h3>
-                            <input placeholder={lang.get("viewAccountLoginEmail")} name="email" id="email" type="email" />
-                            <br/>
-                            <input placeholder={lang.get("viewAccountLoginPassword")} name="password" id="password" type="password" />
-                            <br/><br/>
-                            <button onclick={ctx.link().callback(|_| Msg::Submit)}>
-                                <Icon icon_id={IconId::LucideLogIn}/>
-                                {" "}
-                                {lang.get("viewAccountLoginSubmit")}
-                            </button>
-                            {self.status.clone()}
+                            {content}
                         </div>
                     </div>
                 </div>
@@ -103,59 +168,122 @@ impl Login {
             return;
         }
 
-        let mut password_hash = [0u8; 32];
-        Argon2::default()
-            .hash_password_into(
-                password.as_bytes(),
-                format!("arlekin{}login", email).as_bytes(),
-                &mut password_hash,
-            )
-            .unwrap();
+        login(
+            email,
+            password,
+            None,
+            EncryptionSetup::Existing,
+            self.props.app_callback.clone(),
+            Self::error_callback(ctx),
+        );
+    }
 
-        let app_callback = self.props.app_callback.clone();
-        let status = ctx.link().callback(Msg::SetStatus);
-
-        api::post("accounts/auth/login")
-            .body(&json!({
-                "email": email,
-                "passwordHash": general_purpose::STANDARD.encode(password_hash)
-            }))
-            .send(move |r: ApiResponse<LoginResponseData>| match r {
-                ApiResponse::Ok(r) => {
-                    let mut message_encryption_hash = [0u8; 128];
-                    Argon2::new(
-                        Algorithm::default(),
-                        Version::default(),
-                        Params::new(65536, 3, 3, None).unwrap(),
-                    )
-                    .hash_password_into(
-                        password.as_bytes(),
-                        format!(
-                            "arlekin{}message",
-                            r.message_encryption_salt
-                                .to_le_bytes()
-                                .iter()
-                                .map(|&x| x as char)
-                                .collect::<String>()
-                        )
-                        .as_bytes(),
-                        &mut message_encryption_hash,
-                    )
-                    .unwrap();
-
-                    api::set_refresh_token(r.refresh_token);
-                    wasm_bindgen_futures::spawn_local(async move {
-                        let a = message_encryption_hash;
-                        encryption::init(&a).await;
-
-                        // TODO: remove this. Move to registration.
-                        encryption::put_new_encryption_block(0).await;
-                    });
-                    app_callback.emit(app::Msg::Login(r.user_id));
-                }
-                ApiResponse::BadRequest(err) => {
-                    status.emit(Status::with_err(err));
+    fn submit_two_factor(&self, ctx: &Context<Self>) {
+        let (email, password) = match &self.two_factor {
+            Some(credentials) => credentials.clone(),
+            None => return,
+        };
+        let code = Input::by_id("two-factor-code").value();
+        if code.trim().is_empty() {
+            return;
+        }
+
+        login(
+            email,
+            password,
+            Some(code.trim().replace(' ', "")),
+            EncryptionSetup::Existing,
+            self.props.app_callback.clone(),
+            Self::error_callback(ctx),
+        );
+    }
+
+    fn error_callback(ctx: &Context<Self>) -> Callback<(String, String, LoginError)> {
+        ctx.link().callback(|(email, password, err)| match err {
+            LoginError::Status(status) => Msg::SetStatus(status),
+            LoginError::TwoFactorCodeRequired => Msg::RequireTwoFactor(email, password),
+        })
+    }
+}
+
+pub(crate) fn password_hash(email: &str, password: &str) -> [u8; 32] {
+    let mut password_hash = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(
+            password.as_bytes(),
+            format!("arlekin{}login", email).as_bytes(),
+            &mut password_hash,
+        )
+        .unwrap();
+    password_hash
+}
+
+fn message_encryption_hash(password: &str, message_encryption_salt: i64) -> [u8; 128] {
+    let mut message_encryption_hash = [0u8; 128];
+    Argon2::new(
+        Algorithm::default(),
+        Version::default(),
+        Params::new(65536, 3, 3, None).unwrap(),
+    )
+    .hash_password_into(
+        password.as_bytes(),
+        format!(
+            "arlekin{}message",
+            message_encryption_salt
+                .to_le_bytes()
+                .iter()
+                .map(|&x| x as char)
+                .collect::<String>()
+        )
+        .as_bytes(),
+        &mut message_encryption_hash,
+    )
+    .unwrap();
+    message_encryption_hash
+}
+
+/// Logs in and initializes the encryption. First login after the registration or the password
+/// reset also creates middle keys and the first encryption block of the account.
+pub(crate) fn login(
+    email: String,
+    password: String,
+    two_factor_code: Option<String>,
+    setup: EncryptionSetup,
+    app_callback: Callback<app::Msg>,
+    error: Callback<(String, String, LoginError)>,
+) {
+    accounts::login(&LoginRequestData {
+        email: email.clone(),
+        password_hash: general_purpose::STANDARD.encode(password_hash(&email, &password)),
+        two_factor_code,
+    })
+    .send(move |r| match r {
+        ApiResponse::Ok(r) => {
+            let message_encryption_hash =
+                message_encryption_hash(&password, r.message_encryption_salt);
+
+            api::set_refresh_token(r.refresh_token);
+            wasm_bindgen_futures::spawn_local(async move {
+                match setup {
+                    EncryptionSetup::Existing => encryption::init(&message_encryption_hash).await,
+                    EncryptionSetup::New(backup) => {
+                        encryption::reset(&message_encryption_hash, backup).await
+                    }
                 }
             });
-    }
+            app_callback.emit(app::Msg::Login(r.user_id));
+        }
+        ApiResponse::BadRequest(err) => {
+            let is_two_factor_required = err
+                .get("twoFactorCode")
+                .unwrap_or(&ErrorDataElement::default())
+                .code
+                == TWO_FACTOR_CODE_REQUIRED;
+            let err = match is_two_factor_required {
+                true => LoginError::TwoFactorCodeRequired,
+                false => LoginError::Status(Status::with_err(err)),
+            };
+            error.emit((email, password, err));
+        }
+    });
 }
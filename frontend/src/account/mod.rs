@@ -1,3 +1,8 @@
+pub mod blocked_users;
 pub mod friends_views;
 pub mod load_user;
 pub mod login;
+pub mod password_reset;
+pub mod profile_popover;
+pub mod registration;
+pub mod user_views;
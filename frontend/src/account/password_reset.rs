@@ -0,0 +1,274 @@
+use base64::{engine::general_purpose, Engine as _};
+use yew::prelude::*;
+
+use crate::{
+    account::{
+        login::{self, EncryptionSetup, LoginError},
+        registration::{self, MIN_PASSWORD_STRENGTH},
+    },
+    api::{
+        client::accounts::{self, EmailRequestData, ResetPasswordRequestData},
+        ApiResponse,
+    },
+    app,
+    direct_messages_views::encryption::{self, KeyBackup},
+    helpers::prelude::*,
+    localization,
+};
+
+pub struct PasswordReset {
+    props: Props,
+    status: Html,
+    /// Email to which the reset token was sent.
+    email: Option<String>,
+    /// Password was already reset, but the login requires two-factor authentication code.
+    two_factor: Option<(String, Option<KeyBackup>)>,
+}
+
+pub enum Msg {
+    SetStatus(Html),
+    Request,
+    Requested(String),
+    Submit,
+    RequireTwoFactor(String, Option<KeyBackup>),
+    SubmitTwoFactor,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub app_callback: Callback<app::Msg>,
+}
+
+impl Component for PasswordReset {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            props: ctx.props().clone(),
+            status: Status::default_html(),
+            email: None,
+            two_factor: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SetStatus(status) => self.status = status,
+            Msg::Request => {
+                self.request(ctx);
+                return false;
+            }
+            Msg::Requested(email) => {
+                self.email = Some(email);
+                self.status = Status::default_html();
+            }
+            Msg::Submit => {
+                self.submit(ctx);
+                return false;
+            }
+            Msg::RequireTwoFactor(password, backup) => {
+                self.two_factor = Some((password, backup));
+                self.status = Status::default_html();
+            }
+            Msg::SubmitTwoFactor => {
+                self.submit_two_factor(ctx);
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+        let app_callback = self.props.app_callback.clone();
+
+        let content = match (&self.email, &self.two_factor) {
+            (None, _) => html! { <>
+                <h3 id="welcome-text">{lang.get("viewAccountPasswordResetText")}</h3>
+                <input placeholder={lang.get("viewAccountLoginEmail")} name="email" id="email" type="email" />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Request)}>
+                    {lang.get("viewAccountPasswordResetRequest")}
+                </button>
+            </> },
+            (Some(_), Some(_)) => html! { <>
+                <h3 id="welcome-text">{lang.get("viewAccountLoginTwoFactorText")}</h3>
+                <input
+                    placeholder={lang.get("viewAccountLoginTwoFactorCode")} name="two-factor-code"
+                    id="two-factor-code" type="text" autocomplete="one-time-code"
+                />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::SubmitTwoFactor)}>
+                    {lang.get("viewAccountLoginSubmit")}
+                </button>
+            </> },
+            (Some(email), None) => html! { <>
+                <h3 id="welcome-text">
+                    {lang.get("viewAccountPasswordResetSent").replace("{email}", email)}
+                </h3>
+                <div class="login-warning">
+                    <p>{lang.get("viewAccountPasswordResetWarning")}</p>
+                    <p>{lang.get("viewAccountPasswordResetBackupHint")}</p>
+                    <input id="key-backup" type="file" />
+                    <input
+                        placeholder={lang.get("viewAccountPasswordResetBackupPassphrase")}
+                        id="key-backup-passphrase" type="password"
+                    />
+                </div>
+                <input placeholder={lang.get("viewAccountPasswordResetToken")} id="reset-token" type="text" />
+                <br/>
+                <input placeholder={lang.get("viewAccountPasswordResetNewPassword")} id="password" type="password" />
+                <br/>
+                <input
+                    placeholder={lang.get("viewAccountRegistrationConfirmPassword")} id="confirm-password"
+                    type="password"
+                />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Submit)}>
+                    {lang.get("viewAccountPasswordResetSubmit")}
+                </button>
+            </> },
+        };
+
+        html! {
+            <>
+                <link rel="stylesheet" href="/static/css/account/login.css" />
+                <div class="login-container">
+                    <div id="login-items-main">
+                        <div id="login-items">
+                            <h1 id="login-header">{lang.get("viewAccountPasswordResetTitle")}</h1>
+                            {content}
+                            {self.status.clone()}
+                            <p class="login-switch">
+                                <a onclick={Callback::from(move |_| app_callback.emit(app::Msg::DisplayLoginView(app::LoginView::Login)))}>{
+                                    lang.get("viewAccountLoginTwoFactorCancel")
+                                }</a>
+                            </p>
+                        </div>
+                    </div>
+                </div>
+            </>
+        }
+    }
+}
+
+impl PasswordReset {
+    fn request(&self, ctx: &Context<Self>) {
+        let email = Input::by_id("email").value();
+        if email.is_empty() {
+            return;
+        }
+
+        let callback = ctx.link().callback(Msg::Requested);
+        let status = ctx.link().callback(Msg::SetStatus);
+        accounts::request_password_reset(&EmailRequestData {
+            email: email.clone(),
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(_) => callback.emit(email),
+            ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+        });
+    }
+
+    fn submit(&self, ctx: &Context<Self>) {
+        let email = match &self.email {
+            Some(email) => email.clone(),
+            None => return,
+        };
+        let token = Input::by_id("reset-token").value();
+        let password = Input::by_id("password").value();
+        let confirm_password = Input::by_id("confirm-password").value();
+        let backup_file = Input::by_id("key-backup").files().and_then(|x| x.get(0));
+        let passphrase = Input::by_id("key-backup-passphrase").value();
+
+        if token.trim().is_empty() || password.is_empty() {
+            return;
+        }
+
+        let status = ctx.link().callback(Msg::SetStatus);
+        if password != confirm_password {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordMismatch",
+            ));
+            return;
+        }
+        if registration::password_strength(&password) < MIN_PASSWORD_STRENGTH {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordTooWeak",
+            ));
+            return;
+        }
+
+        let app_callback = self.props.app_callback.clone();
+        let error = Self::error_callback(ctx);
+        wasm_bindgen_futures::spawn_local(async move {
+            // Check the backup before the password is changed, so a wrong passphrase can be fixed.
+            let backup = match backup_file {
+                Some(file) => {
+                    let bytes = File::to_bytes(&file).await;
+                    match encryption::import_key_backup(&bytes, &passphrase).await {
+                        Some(backup) => Some(backup),
+                        None => {
+                            status.emit(Status::with_err_key(
+                                "viewAccountPasswordResetBackupInvalid",
+                            ));
+                            return;
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            let response = accounts::reset_password(&ResetPasswordRequestData {
+                email: email.clone(),
+                token: token.trim().to_owned(),
+                password_hash: general_purpose::STANDARD
+                    .encode(login::password_hash(&email, &password)),
+            })
+            .send_async()
+            .await;
+            match response {
+                ApiResponse::Ok(_) => login::login(
+                    email,
+                    password,
+                    None,
+                    EncryptionSetup::New(backup.clone()),
+                    app_callback,
+                    error.reform(move |err| (err, backup.clone())),
+                ),
+                ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+            };
+        });
+    }
+
+    fn submit_two_factor(&self, ctx: &Context<Self>) {
+        let (email, (password, backup)) = match (&self.email, &self.two_factor) {
+            (Some(email), Some(two_factor)) => (email.clone(), two_factor.clone()),
+            _ => return,
+        };
+        let code = Input::by_id("two-factor-code").value();
+        if code.trim().is_empty() {
+            return;
+        }
+
+        login::login(
+            email,
+            password,
+            Some(code.trim().replace(' ', "")),
+            EncryptionSetup::New(backup.clone()),
+            self.props.app_callback.clone(),
+            Self::error_callback(ctx).reform(move |err| (err, backup.clone())),
+        );
+    }
+
+    fn error_callback(
+        ctx: &Context<Self>,
+    ) -> Callback<((String, String, LoginError), Option<KeyBackup>)> {
+        ctx.link()
+            .callback(|((_, password, err), backup)| match err {
+                LoginError::Status(status) => Msg::SetStatus(status),
+                LoginError::TwoFactorCodeRequired => Msg::RequireTwoFactor(password, backup),
+            })
+    }
+}
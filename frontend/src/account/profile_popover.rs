@@ -0,0 +1,329 @@
+use yew::prelude::*;
+
+use crate::{
+    account::{
+        blocked_users,
+        friends_views::{friend_requests, friends},
+        load_user::{self, use_user, UserState},
+        user_views::{UserAvatar, UserName},
+    },
+    api::{
+        client::accounts::{self, FriendRequestsSendRequestData, UserQueryData},
+        AbortHandle, ApiResponse, Platform,
+    },
+    app::App,
+    direct_messages_views::direct_channels,
+    helpers::prelude::*,
+    localization, store,
+};
+
+const POPOVER_WIDTH: i32 = 320;
+const POPOVER_HEIGHT: i32 = 420;
+
+/// Event which opens the profile of the user at the position.
+#[derive(Clone)]
+struct OpenProfile(i64, i32, i32);
+
+/// Opens profile of the user next to the mouse cursor.
+pub fn open(user_id: i64, event: &MouseEvent) {
+    event.stop_propagation();
+    store::emit(OpenProfile(user_id, event.client_x(), event.client_y()));
+}
+
+pub struct ProfilePopover {
+    opened: Option<(i64, i32, i32)>,
+    relation: Option<Relation>,
+    /// Cancels loading of the previously opened profile.
+    abort: AbortHandle,
+    _subscription: store::Subscription,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Relation {
+    is_friend: bool,
+    is_request_sent: bool,
+    is_request_received: bool,
+    mutual_friends: Vec<i64>,
+}
+
+pub enum Msg {
+    Open(i64, i32, i32),
+    Close,
+    Load(i64, Relation),
+    Reload,
+    Message,
+    AddFriend(String),
+    AcceptFriend,
+    RemoveFriend,
+    Block,
+    Unblock,
+}
+
+#[derive(Properties, Clone, PartialEq)]
+struct ProfileViewProps {
+    user_id: i64,
+    relation: Option<Relation>,
+    callback: Callback<Msg>,
+}
+
+impl Component for ProfilePopover {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            opened: None,
+            relation: None,
+            abort: AbortHandle::default(),
+            _subscription: store::listen(
+                ctx.link()
+                    .callback(|OpenProfile(user_id, x, y)| Msg::Open(user_id, x, y)),
+            ),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let user_id = match (&msg, self.opened) {
+            (Msg::Open(user_id, x, y), _) => {
+                self.opened = Some((*user_id, *x, *y));
+                self.relation = None;
+                self.abort = AbortHandle::default();
+                self.load(ctx);
+                return true;
+            }
+            (Msg::Close, _) => {
+                self.opened = None;
+                self.abort.abort();
+                return true;
+            }
+            (_, Some((user_id, _, _))) => user_id,
+            (_, None) => return false,
+        };
+
+        let reload = ctx.link().callback(|_: ()| Msg::Reload);
+        match msg {
+            Msg::Load(loaded_user_id, relation) => {
+                if loaded_user_id != user_id {
+                    return false;
+                }
+                self.relation = Some(relation);
+            }
+            Msg::Reload => {
+                self.load(ctx);
+                return false;
+            }
+            Msg::Message => {
+                direct_channels::open_with_user(user_id, |_| {
+                    log::error!("Failed to open direct channel.")
+                });
+                self.opened = None;
+            }
+            Msg::AddFriend(username) => {
+                accounts::send_friend_request(&FriendRequestsSendRequestData {
+                    platform: Platform::Native,
+                    user_identifier: username,
+                })
+                .send(move |r| match r {
+                    ApiResponse::Ok(_) => {
+                        friend_requests::update(|requests| requests.sent.push(user_id));
+                        reload.emit(());
+                    }
+                    ApiResponse::BadRequest(_) => log::error!("Failed to send friend request."),
+                });
+                return false;
+            }
+            Msg::AcceptFriend => {
+                friend_requests::accept(user_id, |_| {
+                    log::error!("Failed to accept friend request.")
+                });
+                self.opened = None;
+            }
+            Msg::RemoveFriend => {
+                friends::remove_friend(user_id, move || reload.emit(()));
+                return false;
+            }
+            Msg::Block => {
+                blocked_users::block(user_id, move || reload.emit(()));
+                return false;
+            }
+            Msg::Unblock => {
+                blocked_users::unblock(user_id, move || reload.emit(()));
+                return false;
+            }
+            Msg::Open(..) | Msg::Close => unreachable!(),
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let (user_id, x, y) = match self.opened {
+            Some(opened) => opened,
+            None => return html! {},
+        };
+
+        // Keep the whole popover inside of the window.
+        let window = WebPage::window();
+        let width = window.inner_width().unwrap().as_f64().unwrap() as i32;
+        let height = window.inner_height().unwrap().as_f64().unwrap() as i32;
+        let left = x.min(width - POPOVER_WIDTH).max(0);
+        let top = y.min(height - POPOVER_HEIGHT).max(0);
+
+        html! {
+            <div class="profile-popover-backdrop" onclick={ctx.link().callback(|_| Msg::Close)}>
+                <div
+                    class="profile-popover"
+                    style={format!("left: {left}px; top: {top}px; width: {POPOVER_WIDTH}px")}
+                    onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                >
+                    <ProfileView
+                        {user_id}
+                        relation={self.relation.clone()}
+                        callback={ctx.link().callback(|m| m)}
+                    />
+                </div>
+            </div>
+        }
+    }
+}
+
+impl ProfilePopover {
+    fn load(&self, ctx: &Context<Self>) {
+        let user_id = match self.opened {
+            Some((user_id, _, _)) => user_id,
+            None => return,
+        };
+        if user_id == App::user_id() {
+            return;
+        }
+
+        // Status shown in the profile is always up to date.
+        load_user::refresh_user(user_id, true);
+
+        let callback = ctx.link().callback(move |r| Msg::Load(user_id, r));
+        let mutual =
+            accounts::get_mutual_friends(&UserQueryData { id: user_id }).abort_with(&self.abort);
+        wasm_bindgen_futures::spawn_local(async move {
+            let friends = match friends::load_friend_ids().await {
+                Some(friends) => friends,
+                None => return,
+            };
+            let mutual = match mutual.try_send_async().await {
+                Ok(ApiResponse::Ok(mutual)) => mutual,
+                _ => return,
+            };
+
+            let (is_request_sent, is_request_received) = friend_requests::relation(user_id);
+            callback.emit(Relation {
+                is_friend: friends.friends.contains(&user_id),
+                is_request_sent,
+                is_request_received,
+                mutual_friends: mutual.friends,
+            });
+        });
+    }
+}
+
+#[function_component(ProfileView)]
+fn profile_view(props: &ProfileViewProps) -> Html {
+    let lang = localization::get_language();
+    let user = match use_user(props.user_id, true) {
+        UserState::Loaded(user) => user,
+        UserState::Missing => return html! { <p>{lang.get("viewProfileNotFound")}</p> },
+        UserState::Loading => return html! { <p>{"Loading..."}</p> },
+    };
+    let callback = props.callback.clone();
+
+    let status = match &user.status {
+        Some(status) => html! { <>
+            <div class="profile-popover-status user-profile">{status.icon_html()}</div>
+            if !status.custom_status.is_empty() {
+                <p class="profile-popover-custom-status">{status.custom_status.clone()}</p>
+            }
+            if !status.rich_presence.is_empty() {
+                <p class="profile-popover-rich-presence">{status.rich_presence.clone()}</p>
+            }
+        </> },
+        None => html! {},
+    };
+
+    let is_me = user.user_id == App::user_id();
+    let is_blocked = blocked_users::is_blocked(user.user_id);
+
+    let action = |msg: fn() -> Msg, translation_key: &str, class: &'static str| {
+        let callback = callback.clone();
+        html! {
+            <button {class} onclick={Callback::from(move |_| callback.emit(msg()))}>{
+                lang.get(translation_key)
+            }</button>
+        }
+    };
+
+    let (actions, mutual) = match (&props.relation, is_me) {
+        (_, true) => (html! {}, html! {}),
+        (None, false) => (html! { <p>{"Loading..."}</p> }, html! {}),
+        (Some(relation), false) => {
+            let friend_action = if is_blocked {
+                html! {}
+            } else if relation.is_friend {
+                action(|| Msg::RemoveFriend, "viewProfileRemoveFriend", "")
+            } else if relation.is_request_received {
+                action(|| Msg::AcceptFriend, "viewProfileAcceptFriend", "")
+            } else if relation.is_request_sent {
+                html! {
+                    <button disabled=true>{lang.get("viewProfileFriendRequestSent")}</button>
+                }
+            } else {
+                let callback = callback.clone();
+                let username = user.username.clone();
+                html! {
+                    <button onclick={Callback::from(move |_| callback.emit(Msg::AddFriend(username.clone())))}>{
+                        lang.get("viewProfileAddFriend")
+                    }</button>
+                }
+            };
+
+            let block_action = match is_blocked {
+                true => action(|| Msg::Unblock, "viewProfileUnblock", ""),
+                false => action(|| Msg::Block, "viewProfileBlock", "profile-popover-danger"),
+            };
+
+            let actions = html! {
+                <div class="profile-popover-actions">
+                    {action(|| Msg::Message, "viewProfileMessage", "")}
+                    {friend_action}
+                    {block_action}
+                </div>
+            };
+
+            let mutual = match relation.mutual_friends.is_empty() {
+                true => html! {},
+                false => html! {
+                    <div class="profile-popover-mutual">
+                        <h4>{lang.get("viewProfileMutualFriends")}{format!(" ({})", relation.mutual_friends.len())}</h4>
+                        {
+                            relation.mutual_friends.iter().map(|user_id| html! {
+                                <div class="profile-popover-mutual-friend">
+                                    <UserAvatar user_id={*user_id} with_profile={true} />
+                                    <UserName user_id={*user_id} with_profile={true} />
+                                </div>
+                            }).collect::<Html>()
+                        }
+                    </div>
+                },
+            };
+
+            (actions, mutual)
+        }
+    };
+
+    html! {
+        <div class="profile-popover-inner">
+            <img class="profile-popover-avatar" src={user.avatar_url.clone()} alt={"avatar"} />
+            {status}
+            <h3 class="profile-popover-name select">{user.name.clone()}</h3>
+            <p class="profile-popover-username select">{"@"}{user.username.clone()}</p>
+            {actions}
+            {mutual}
+        </div>
+    }
+}
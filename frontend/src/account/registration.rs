@@ -0,0 +1,296 @@
+use base64::{engine::general_purpose, Engine as _};
+use yew::prelude::*;
+
+use crate::{
+    account::login::{self, EncryptionSetup, LoginError},
+    api::{
+        client::accounts::{self, EmailRequestData, RegisterRequestData, VerifyEmailRequestData},
+        ApiResponse,
+    },
+    app,
+    helpers::prelude::*,
+    localization,
+};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+pub(crate) const MIN_PASSWORD_STRENGTH: usize = 2;
+
+pub struct Registration {
+    props: Props,
+    status: Html,
+    password_strength: Option<usize>,
+    verification: Option<(String, String)>,
+}
+
+pub enum Msg {
+    SetStatus(Html),
+    PasswordInput,
+    Submit,
+    RequireVerification(String, String),
+    Verify,
+    ResendVerification,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub app_callback: Callback<app::Msg>,
+}
+
+impl Component for Registration {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            props: ctx.props().clone(),
+            status: Status::default_html(),
+            password_strength: None,
+            verification: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SetStatus(status) => self.status = status,
+            Msg::PasswordInput => {
+                let password = Input::by_id("password").value();
+                self.password_strength = match password.is_empty() {
+                    true => None,
+                    false => Some(password_strength(&password)),
+                };
+            }
+            Msg::Submit => {
+                self.submit(ctx);
+                return false;
+            }
+            Msg::RequireVerification(email, password) => {
+                self.verification = Some((email, password));
+                self.status = Status::default_html();
+            }
+            Msg::Verify => {
+                self.verify(ctx);
+                return false;
+            }
+            Msg::ResendVerification => {
+                self.resend_verification(ctx);
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+        let app_callback = self.props.app_callback.clone();
+
+        let content = match &self.verification {
+            Some((email, _)) => html! { <>
+                <h3 id="welcome-text">
+                    {lang.get("viewAccountRegistrationVerificationText").replace("{email}", email)}
+                </h3>
+                <input placeholder={lang.get("viewAccountRegistrationVerificationCode")} id="verification-code" type="text" />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Verify)}>
+                    {lang.get("viewAccountRegistrationVerify")}
+                </button>
+                {self.status.clone()}
+                <p class="login-switch">
+                    <a onclick={ctx.link().callback(|_| Msg::ResendVerification)}>{
+                        lang.get("viewAccountRegistrationResend")
+                    }</a>
+                </p>
+            </> },
+            None => html! { <>
+                <h3 id="welcome-text">{lang.get("viewAccountRegistrationWelcomeText")}</h3>
+                <input placeholder={lang.get("viewAccountLoginEmail")} name="email" id="email" type="email" />
+                <br/>
+                <input placeholder={lang.get("viewAccountRegistrationUsername")} name="username" id="username" type="text" />
+                <br/>
+                <input
+                    placeholder={lang.get("viewAccountLoginPassword")} name="password" id="password" type="password"
+                    oninput={ctx.link().callback(|_| Msg::PasswordInput)}
+                />
+                {self.password_strength_view()}
+                <input
+                    placeholder={lang.get("viewAccountRegistrationConfirmPassword")} name="confirm-password"
+                    id="confirm-password" type="password"
+                />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Submit)}>
+                    {lang.get("viewAccountRegistrationSubmit")}
+                </button>
+                {self.status.clone()}
+                <p class="login-switch">
+                    {lang.get("viewAccountRegistrationHaveAccount")}
+                    {" "}
+                    <a onclick={Callback::from(move |_| app_callback.emit(app::Msg::DisplayLoginView(app::LoginView::Login)))}>{
+                        lang.get("viewAccountRegistrationLogin")
+                    }</a>
+                </p>
+            </> },
+        };
+
+        html! {
+            <>
+                <link rel="stylesheet" href="/static/css/account/login.css" />
+                <div class="login-container">
+                    <div id="login-items-main">
+                        <div id="login-items">
+                            <h1 id="login-header">{lang.get("viewAccountRegistrationTitle")}</h1>
+                            {content}
+                        </div>
+                    </div>
+                </div>
+            </>
+        }
+    }
+}
+
+impl Registration {
+    fn password_strength_view(&self) -> Html {
+        let strength = match self.password_strength {
+            Some(strength) => strength,
+            None => return html! {},
+        };
+
+        let lang = localization::get_language();
+        let translation_key = match strength {
+            0 | 1 => "viewAccountRegistrationPasswordWeak",
+            2 => "viewAccountRegistrationPasswordFair",
+            3 => "viewAccountRegistrationPasswordGood",
+            _ => "viewAccountRegistrationPasswordStrong",
+        };
+
+        html! {
+            <div class="registration-strength">
+                <div class={format!("registration-strength-bar registration-strength-{strength}")} />
+                <span>{lang.get(translation_key)}</span>
+            </div>
+        }
+    }
+
+    fn submit(&self, ctx: &Context<Self>) {
+        let email = Input::by_id("email").value();
+        let username = Input::by_id("username").value();
+        let password = Input::by_id("password").value();
+        let confirm_password = Input::by_id("confirm-password").value();
+
+        if email.is_empty() || username.is_empty() || password.is_empty() {
+            return;
+        }
+
+        let status = ctx.link().callback(Msg::SetStatus);
+        if password != confirm_password {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordMismatch",
+            ));
+            return;
+        }
+        if password_strength(&password) < MIN_PASSWORD_STRENGTH {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordTooWeak",
+            ));
+            return;
+        }
+
+        let app_callback = self.props.app_callback.clone();
+        let verification = ctx
+            .link()
+            .callback(|(email, password)| Msg::RequireVerification(email, password));
+
+        accounts::register(&RegisterRequestData {
+            email: email.clone(),
+            username: username.trim().to_owned(),
+            password_hash: general_purpose::STANDARD
+                .encode(login::password_hash(&email, &password)),
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(r) => match r.email_verification_required {
+                true => verification.emit((email, password)),
+                false => login::login(
+                    email,
+                    password,
+                    None,
+                    EncryptionSetup::New(None),
+                    app_callback,
+                    login_error(status),
+                ),
+            },
+            ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+        });
+    }
+
+    fn verify(&self, ctx: &Context<Self>) {
+        let (email, password) = match &self.verification {
+            Some(verification) => verification.clone(),
+            None => return,
+        };
+        let code = Input::by_id("verification-code").value();
+        if code.is_empty() {
+            return;
+        }
+
+        let app_callback = self.props.app_callback.clone();
+        let status = ctx.link().callback(Msg::SetStatus);
+
+        accounts::verify_email(&VerifyEmailRequestData {
+            email: email.clone(),
+            code: code.trim().to_owned(),
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(_) => login::login(
+                email,
+                password,
+                None,
+                EncryptionSetup::New(None),
+                app_callback,
+                login_error(status),
+            ),
+            ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+        });
+    }
+
+    fn resend_verification(&self, ctx: &Context<Self>) {
+        let email = match &self.verification {
+            Some((email, _)) => email.clone(),
+            None => return,
+        };
+
+        let status = ctx.link().callback(Msg::SetStatus);
+        accounts::resend_verification(&EmailRequestData { email }).send(move |r| match r {
+            ApiResponse::Ok(_) => status.emit(Status::with_ok("viewAccountRegistrationResent")),
+            ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+        });
+    }
+}
+
+/// New account can not have two-factor authentication enabled yet.
+fn login_error(status: Callback<Html>) -> Callback<(String, String, LoginError)> {
+    status.reform(|(_, _, err)| match err {
+        LoginError::Status(status) => status,
+        LoginError::TwoFactorCodeRequired => Status::default_html(),
+    })
+}
+
+/// Rough estimate of the password strength from 0 to 4.
+pub(crate) fn password_strength(password: &str) -> usize {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return 0;
+    }
+
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&x| x)
+    .count();
+
+    let mut strength = classes.saturating_sub(1);
+    if password.chars().count() >= 12 {
+        strength += 1;
+    }
+    strength.min(4)
+}
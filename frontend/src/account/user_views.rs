@@ -0,0 +1,116 @@
+use yew::prelude::*;
+
+use crate::localization;
+
+use super::{
+    load_user::{use_user, UserState},
+    profile_popover,
+};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct UserAvatarProps {
+    pub user_id: i64,
+    #[prop_or(classes!("user-avatar"))]
+    pub class: Classes,
+    /// Opens the profile of the user when clicked.
+    #[prop_or_default]
+    pub with_profile: bool,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct UserNameProps {
+    pub user_id: i64,
+    #[prop_or_default]
+    pub class: Classes,
+    /// Opens the profile of the user when clicked.
+    #[prop_or_default]
+    pub with_profile: bool,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct UserBadgeProps {
+    pub user_id: i64,
+    /// Shows the status icon, and the rich presence instead of the username when it is set.
+    #[prop_or_default]
+    pub with_status: bool,
+    /// Opens the profile of the user when the avatar or the name is clicked.
+    #[prop_or_default]
+    pub with_profile: bool,
+}
+
+#[function_component(UserAvatar)]
+pub fn user_avatar(props: &UserAvatarProps) -> Html {
+    match use_user(props.user_id, false).loaded() {
+        Some(user) => html! {
+            <img
+                class={classes!(props.class.clone(), props.with_profile.then_some("clickable"))}
+                src={user.avatar_url.clone()}
+                alt={"avatar"}
+                onclick={open_profile(props.user_id, props.with_profile)}
+            />
+        },
+        None => html! {},
+    }
+}
+
+#[function_component(UserName)]
+pub fn user_name(props: &UserNameProps) -> Html {
+    match use_user(props.user_id, false) {
+        UserState::Loaded(user) => html! {
+            <span
+                class={classes!(props.class.clone(), props.with_profile.then_some("clickable"))}
+                onclick={open_profile(props.user_id, props.with_profile)}
+            >
+                {user.name.clone()}
+            </span>
+        },
+        UserState::Missing => html! {
+            <span class={props.class.clone()}>{localization::get_language().get("viewProfileNotFound")}</span>
+        },
+        UserState::Loading => html! {},
+    }
+}
+
+/// Avatar, name and username of the user.
+#[function_component(UserBadge)]
+pub fn user_badge(props: &UserBadgeProps) -> Html {
+    let user = match use_user(props.user_id, props.with_status) {
+        UserState::Loaded(user) => user,
+        UserState::Missing => {
+            return html! { {localization::get_language().get("viewProfileNotFound")} }
+        }
+        UserState::Loading => return html! { {"Loading..."} },
+    };
+
+    let status = user.status.as_ref().filter(|_| props.with_status);
+    let info = match status {
+        Some(status) if !status.custom_status.is_empty() => status.custom_status.clone(),
+        Some(status) if !status.rich_presence.is_empty() => status.rich_presence.clone(),
+        _ => format!("@{}", user.username),
+    };
+
+    let clickable = props.with_profile.then_some("clickable");
+    let open_profile = open_profile(props.user_id, props.with_profile);
+
+    html! {
+        <div class="user-profile">
+            <img
+                class={classes!("user-avatar", clickable)}
+                src={user.avatar_url.clone()}
+                alt={"avatar"}
+                onclick={open_profile.clone()}
+            />
+            if let Some(status) = status {
+                {status.icon_html()}
+            }
+            <div class="user-content">
+                <p class={classes!("user-name", clickable)} onclick={open_profile}>{user.name.clone()}</p>
+                <p class="user-info">{info}</p>
+            </div>
+        </div>
+    }
+}
+
+fn open_profile(user_id: i64, with_profile: bool) -> Option<Callback<MouseEvent>> {
+    with_profile.then(|| Callback::from(move |e: MouseEvent| profile_popover::open(user_id, &e)))
+}
@@ -0,0 +1,31 @@
+use web_sys::{AbortController, AbortSignal};
+
+/// Cancels requests sent with it when aborted or dropped. Components keep it as a field, so
+/// their pending requests stop together with them.
+pub struct AbortHandle {
+    controller: AbortController,
+}
+
+impl AbortHandle {
+    pub fn abort(&self) {
+        self.controller.abort();
+    }
+
+    pub(super) fn signal(&self) -> AbortSignal {
+        self.controller.signal()
+    }
+}
+
+impl Default for AbortHandle {
+    fn default() -> Self {
+        Self {
+            controller: AbortController::new().expect("Unable to create AbortController."),
+        }
+    }
+}
+
+impl Drop for AbortHandle {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{account::load_user::User, api::Platform};
+
+use super::NoContent;
+
+endpoints! {
+    login: Post "accounts/auth/login", LoginRequestData => LoginResponseData;
+    logout: Get "accounts/auth/logout", () => NoContent;
+    register: Post "accounts/auth/register", RegisterRequestData => RegisterResponseData;
+    verify_email: Post "accounts/auth/verifyemail", VerifyEmailRequestData => NoContent;
+    resend_verification: Post "accounts/auth/resendverification", EmailRequestData => NoContent;
+    request_password_reset: Post "accounts/auth/resetpassword/request", EmailRequestData => NoContent;
+    reset_password: Post "accounts/auth/resetpassword", ResetPasswordRequestData => NoContent;
+    /// Address and token of the notifier web socket.
+    get_ws: Get "accounts/getws", () => GetWsResponseData;
+
+    /// Unknown users are omitted from the response.
+    get_users: Get "accounts/users", UsersQueryData => UsersResponseData;
+    update_user: Post "accounts/user/update", UserUpdateRequestData => User;
+    /// Body is the PNG image of the avatar.
+    put_avatar: Put "accounts/user/avatar", raw => User;
+    put_status: Put "accounts/status", StatusRequestData => NoContent;
+
+    get_friends: Get "accounts/friends", () => FriendsLoadResponseData;
+    get_mutual_friends: Get "accounts/friends/mutual", UserQueryData => FriendsLoadResponseData;
+    remove_friend: Delete "accounts/friends", FriendRemoveRequestData => NoContent;
+    get_friend_requests: Get "accounts/friendrequests", () => FriendRequestsLoadResponseData;
+    send_friend_request: Put "accounts/friendrequests", FriendRequestsSendRequestData => FriendRequestsSendResponseData;
+    accept_friend_request: Post "accounts/friendrequests", FriendRequestsRespondRequestData => NoContent;
+    /// Rejects received or cancels sent friend request.
+    reject_friend_request: Delete "accounts/friendrequests", FriendRequestsRespondRequestData => NoContent;
+
+    get_blocked_users: Get "accounts/blocked", () => BlockedUsersLoadResponseData;
+    block_user: Put "accounts/blocked", UserIdRequestData => NoContent;
+    unblock_user: Delete "accounts/blocked", UserIdRequestData => NoContent;
+
+    get_two_factor: Get "accounts/twofactor", () => TwoFactorResponseData;
+    setup_two_factor: Post "accounts/twofactor/setup", () => TwoFactorSetupResponseData;
+    enable_two_factor: Post "accounts/twofactor/enable", TwoFactorCodeRequestData => TwoFactorEnableResponseData;
+    /// Code can be also one of the recovery codes.
+    disable_two_factor: Post "accounts/twofactor/disable", TwoFactorCodeRequestData => NoContent;
+
+    get_sessions: Get "accounts/sessions", () => SessionsResponseData;
+    revoke_session: Delete "accounts/sessions", SessionIdRequestData => NoContent;
+    revoke_other_sessions: Post "accounts/sessions/revokeothers", () => NoContent;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginRequestData {
+    pub email: String,
+    pub password_hash: String,
+    pub two_factor_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResponseData {
+    pub user_id: i64,
+    pub refresh_token: Uuid,
+    pub message_encryption_salt: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterRequestData {
+    pub email: String,
+    pub username: String,
+    pub password_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterResponseData {
+    pub email_verification_required: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailRequestData {
+    pub email: String,
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailRequestData {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequestData {
+    pub email: String,
+    pub token: String,
+    pub password_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetWsResponseData {
+    pub token: Uuid,
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserQueryData {
+    pub id: i64,
+}
+
+/// Users are identified by comma separated `ids`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsersQueryData {
+    pub ids: String,
+    pub with_status: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsersResponseData {
+    pub users: Vec<User>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdRequestData {
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserUpdateRequestData {
+    pub name: String,
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusRequestData {
+    pub status: i32,
+    pub rich_presence: String,
+    pub custom_status: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendsLoadResponseData {
+    pub friends: Vec<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRemoveRequestData {
+    pub friend_user_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRequestsLoadResponseData {
+    pub received: Vec<i64>,
+    pub sent: Vec<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRequestsSendRequestData {
+    pub platform: Platform,
+    pub user_identifier: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRequestsSendResponseData {
+    pub receiver_user_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendRequestsRespondRequestData {
+    pub requested_friend_user_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedUsersLoadResponseData {
+    pub blocked: Vec<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorResponseData {
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorSetupResponseData {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorCodeRequestData {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorEnableResponseData {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponseData {
+    pub sessions: Vec<SessionResponseData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponseData {
+    pub session_id: i64,
+    pub device: String,
+    pub platform: Platform,
+    pub ip_address: String,
+    pub last_seen: i64,
+    pub is_current: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIdRequestData {
+    pub session_id: i64,
+}
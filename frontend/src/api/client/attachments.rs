@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+endpoints! {
+    /// Reserves the attachment, which is then uploaded with the token to the storage domain.
+    create_direct_bucket: Put "attachments/direct/bucket", CreateBucketRequestData => CreateBucketResponseData;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBucketRequestData {
+    pub size: i64,
+    pub name: String,
+    pub alternate_text_nonce: String,
+    pub encrypted_alternate_text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBucketResponseData {
+    pub attachment_id: i64,
+    pub name: String,
+    pub storage_domain: String,
+    pub token: String,
+}
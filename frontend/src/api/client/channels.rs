@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::Platform;
+
+use super::{accounts::UserIdRequestData, NoContent};
+
+endpoints! {
+    get_direct_channels: Get "channels/direct", () => DirectChannelsLoadResponseData;
+    /// Channel is created when it does not exist yet.
+    get_direct_channel_id: Post "channels/direct/getchannelidfromuserid", UserIdRequestData => GetChannelIdFromUserIdResponseData;
+
+    get_messages: Get "channels/direct/messages", MessagesGetRequestData => MessagesGetResultData;
+    send_message: Put "channels/direct/messages", MessagesPutRequestData => MessagesPutResponseData;
+    ack_messages: Post "channels/direct/messages/ack", MessagesAckRequestData => NoContent;
+
+    put_encryption_block: Put "channels/direct/encryption", PutEncryptionBlockRequestData => PutEncryptionBlockResponseData;
+    get_public_keys: Post "channels/direct/encryption/getpublickeys", DirectChannelRequestData => EncryptionPublicKeyResponseData;
+    get_private_key: Post "channels/direct/encryption/getprivatekey", EncryptionPrivateKeyRequestData => EncryptionPrivateKeyResponseData;
+    get_middle_keys: Post "channels/direct/encryption/getmiddlekeys", () => MiddleKeysData;
+    put_middle_keys: Put "channels/direct/encryption/putmiddlekeys", MiddleKeysData => NoContent;
+    put_encryption_keys: Put "channels/direct/encryption/keys", EncryptionKeysPutRequestData => EncryptionKeysPutResponseData;
+    /// Encryption key zero is the newest key of the channel.
+    get_encrypted_key: Post "channels/direct/encryption/keys/getencryptedkey", EncryptionKeyRequestData => EncryptionKeysGetEncryptedKeyResponseData;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectChannelsLoadResponseData {
+    pub direct_channels: Vec<DirectChannelResponseData>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectChannelResponseData {
+    pub unread_count: i64,
+    pub recent_activity: i64,
+    pub is_group: bool,
+    pub user_id: i64,
+    pub group_data: Option<GroupResponseData>,
+    pub direct_channel_id: i64,
+    pub last_read_direct_message_id: i64,
+    /// Unread messages which mention the current user, counted only on this device.
+    #[serde(skip)]
+    pub mention_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupResponseData {
+    pub name: String,
+    pub avatar_url: String,
+    pub user_count: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetChannelIdFromUserIdResponseData {
+    pub channel_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectChannelRequestData {
+    pub direct_channel_id: i64,
+}
+
+/// Loads messages older than `before_direct_message_id`, or newer than
+/// `after_direct_message_id`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesGetRequestData {
+    pub direct_channel_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_direct_message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_direct_message_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesGetResultData {
+    pub messages: Vec<MessagesGetElementResultData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesGetElementResultData {
+    pub direct_message_id: i64,
+    pub author_user_id: i64,
+    pub encryption_key_id: i64,
+    pub nonce: String,
+    pub encrypted_text: String,
+    pub edited: bool,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesPutRequestData {
+    pub direct_channel_id: i64,
+    pub encryption_key_id: i64,
+    pub nonce: String,
+    pub encrypted_text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesPutResponseData {
+    pub direct_message_id: i64,
+    pub send_new_encryption_key: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesAckRequestData {
+    pub direct_channel_id: i64,
+    pub last_read_direct_message_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutEncryptionBlockRequestData {
+    pub direct_channel_id: i64,
+    pub public_key: String,
+    pub nonce: String,
+    pub encrypted_private_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutEncryptionBlockResponseData {
+    pub encryption_block_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionPublicKeyResponseData {
+    pub public_keys: Vec<EncryptionPublicKeyElementResponseData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionPublicKeyElementResponseData {
+    pub platform: Platform,
+    pub encryption_block_id: i64,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionPrivateKeyRequestData {
+    pub direct_channel_id: i64,
+    pub encryption_block_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionPrivateKeyResponseData {
+    pub nonce: String,
+    pub encrypted_private_key: String,
+}
+
+/// Middle keys of the account and their copy encrypted by the encryption block hash.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MiddleKeysData {
+    pub keys: String,
+    pub encrypted_keys: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeysPutRequestData {
+    pub direct_channel_id: i64,
+    pub key_data: Vec<EncryptionKeysPutDataElement>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeysPutDataElement {
+    pub encryption_block_id: i64,
+    pub encrypted_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeysPutResponseData {
+    pub encryption_block_id: i64,
+    pub encryption_key_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeyRequestData {
+    pub direct_channel_id: i64,
+    pub encryption_key_id: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeysGetEncryptedKeyResponseData {
+    pub encryption_block_id: i64,
+    pub encryption_key_id: i64,
+    pub encrypted_key: String,
+}
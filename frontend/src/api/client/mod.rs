@@ -0,0 +1,527 @@
+//! Typed client of the API. Every endpoint has one function, which knows its path and the types
+//! of its request and response. Endpoints are described also in `openapi.json`, against which
+//! the contract test checks them.
+
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, de::IgnoredAny, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::App;
+
+use super::{
+    AbortHandle, ApiRequest, ApiRequestKind, ApiResponse, ErrorData, RequestError, API_ENDPOINT,
+};
+
+/// Declares functions of endpoints in the form
+/// `name: Kind "path", Request => Response;`, where the request is `()` for endpoints without
+/// any data and `raw` for endpoints with binary body. Data of `Get` requests are sent in query.
+macro_rules! endpoints {
+    (@function $(#[$meta:meta])* $name:ident $kind:ident $path:literal () $response:ty) => {
+        $(#[$meta])*
+        pub fn $name() -> $crate::api::client::TypedRequest<$response> {
+            $crate::api::client::TypedRequest::new($crate::api::ApiRequestKind::$kind, $path, &())
+        }
+    };
+    (@function $(#[$meta:meta])* $name:ident $kind:ident $path:literal raw $response:ty) => {
+        $(#[$meta])*
+        pub fn $name(body: Vec<u8>) -> $crate::api::client::TypedRequest<$response> {
+            $crate::api::client::TypedRequest::new_raw($crate::api::ApiRequestKind::$kind, $path, body)
+        }
+    };
+    (@function $(#[$meta:meta])* $name:ident $kind:ident $path:literal $request:ident $response:ty) => {
+        $(#[$meta])*
+        pub fn $name(request: &$request) -> $crate::api::client::TypedRequest<$response> {
+            $crate::api::client::TypedRequest::new($crate::api::ApiRequestKind::$kind, $path, request)
+        }
+    };
+    (@request ()) => { () };
+    (@request raw) => { () };
+    (@request $request:ident) => { $request };
+    (@raw raw) => { true };
+    (@raw $request:tt) => { false };
+    ($(
+        $(#[$meta:meta])*
+        $name:ident: $kind:ident $path:literal, $request:tt => $response:ty;
+    )*) => {
+        $(
+            endpoints!(@function $(#[$meta])* $name $kind $path $request $response);
+        )*
+
+        #[cfg(test)]
+        pub(super) fn visit_endpoints(visitor: &mut impl $crate::api::client::EndpointVisitor) {
+            $(
+                visitor.visit::<endpoints!(@request $request), $response>(
+                    stringify!($name),
+                    $crate::api::ApiRequestKind::$kind,
+                    $path,
+                    endpoints!(@raw $request),
+                );
+            )*
+        }
+    };
+}
+
+pub mod accounts;
+pub mod attachments;
+pub mod channels;
+
+/// Request with known type of its response.
+pub struct TypedRequest<T> {
+    request: ApiRequest,
+    response: PhantomData<T>,
+}
+
+/// Response of endpoints without any data, content of the body is ignored.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoContent;
+
+impl<T: DeserializeOwned + 'static> TypedRequest<T> {
+    fn new<R: Serialize>(kind: ApiRequestKind, endpoint: &str, request: &R) -> Self {
+        let is_get = matches!(kind, ApiRequestKind::Get);
+        let mut api_request = ApiRequest::new(kind, endpoint);
+
+        match serde_json::to_value(request).unwrap() {
+            Value::Null => (),
+            Value::Object(fields) if is_get => {
+                let query = fields
+                    .iter()
+                    .filter_map(|(name, value)| match value {
+                        Value::Null => None,
+                        Value::String(value) => Some((name.as_str(), value.clone())),
+                        value => Some((name.as_str(), value.to_string())),
+                    })
+                    .collect::<Vec<_>>();
+                api_request = api_request.query(query);
+            }
+            value => api_request = api_request.body(&value),
+        };
+
+        Self {
+            request: api_request,
+            response: PhantomData,
+        }
+    }
+
+    fn new_raw(kind: ApiRequestKind, endpoint: &str, body: Vec<u8>) -> Self {
+        Self {
+            request: ApiRequest::new(kind, endpoint).body_raw(body),
+            response: PhantomData,
+        }
+    }
+
+    /// Time in milliseconds after which a single attempt is cancelled.
+    pub fn timeout(mut self, milliseconds: i32) -> Self {
+        self.request = self.request.timeout(milliseconds);
+        self
+    }
+
+    /// Number of retries after network errors, timeouts and server errors.
+    pub fn retries(mut self, max_retries: u32) -> Self {
+        self.request = self.request.retries(max_retries);
+        self
+    }
+
+    /// Retries until the request succeeds or is aborted, see [`ApiRequest::retry_forever`].
+    pub fn retry_forever(mut self) -> Self {
+        self.request = self.request.retry_forever();
+        self
+    }
+
+    pub fn abort_with(mut self, handle: &AbortHandle) -> Self {
+        self.request = self.request.abort_with(handle);
+        self
+    }
+
+    /// Path of the endpoint with the query, which identifies the response in the cache.
+    pub(super) fn cache_key(&self) -> String {
+        let request = &self.request;
+        let mut key = request.endpoint[API_ENDPOINT.len()..].to_owned();
+        if let Some(query) = &request.query {
+            let query = query
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>();
+            key = format!("{key}?{}", query.join("&"));
+        }
+        key
+    }
+
+    /// Sends the request and waits for the response. Failure of the request is returned as
+    /// [`ApiResponse::BadRequest`], use [`Self::try_send_async`] to tell them apart.
+    pub async fn send_async(&self) -> ApiResponse<T> {
+        match self.try_send_async().await {
+            Ok(response) => response,
+            Err(err) => Self::failed(err),
+        }
+    }
+
+    pub async fn try_send_async(&self) -> Result<ApiResponse<T>, RequestError> {
+        let response = self.request.try_send_async().await?;
+        Ok(match response.status() {
+            200 => ApiResponse::Ok(Self::parse(&response.text().await.unwrap())),
+            400 => ApiResponse::BadRequest(response.json::<ErrorData>().await.unwrap().errors),
+            _ => unreachable!(),
+        })
+    }
+
+    fn parse(text: &str) -> T {
+        // Endpoints without content may respond with an empty body.
+        let text = match text.is_empty() {
+            true => "null",
+            false => text,
+        };
+        serde_json::from_str(text).unwrap()
+    }
+
+    /// Sends the request in the background. Callback is not called when the request is aborted,
+    /// other failures are passed to it as [`ApiResponse::BadRequest`].
+    pub fn send<F>(self, callback: F)
+    where
+        F: FnOnce(ApiResponse<T>) + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(async move {
+            match self.try_send_async().await {
+                Ok(response) => callback(response),
+                Err(RequestError::Aborted) => (),
+                Err(err) => callback(Self::failed(err)),
+            }
+        });
+    }
+
+    fn failed(err: RequestError) -> ApiResponse<T> {
+        if let RequestError::Unauthorized = err {
+            App::logout_without_api();
+        }
+        ApiResponse::BadRequest(err.to_errors())
+    }
+}
+
+impl Serialize for NoContent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+impl<'de> Deserialize<'de> for NoContent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        IgnoredAny::deserialize(deserializer).map(|_| NoContent)
+    }
+}
+
+#[cfg(test)]
+trait EndpointVisitor {
+    fn visit<Req, Res>(&mut self, name: &str, kind: ApiRequestKind, path: &str, raw: bool)
+    where
+        Req: Serialize + DeserializeOwned,
+        Res: Serialize + DeserializeOwned + 'static;
+}
+
+#[cfg(test)]
+fn visit_endpoints(visitor: &mut impl EndpointVisitor) {
+    accounts::visit_endpoints(visitor);
+    attachments::visit_endpoints(visitor);
+    channels::visit_endpoints(visitor);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde_json::Map;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    const OPENAPI: &str = include_str!("../../../openapi.json");
+
+    /// Server which answers with the examples of the OpenAPI description and validates requests
+    /// against its schemas.
+    struct MockServer {
+        spec: Value,
+        visited: HashSet<(String, String)>,
+        errors: Vec<String>,
+    }
+
+    impl MockServer {
+        fn new() -> Self {
+            Self {
+                spec: serde_json::from_str(OPENAPI).unwrap(),
+                visited: HashSet::new(),
+                errors: Vec::new(),
+            }
+        }
+
+        fn operation(&self, method: &str, path: &str) -> Result<&Value, String> {
+            match self.spec["paths"][format!("/{path}")].get(method) {
+                Some(operation) => Ok(operation),
+                None => Err(format!("{method} /{path} is not described")),
+            }
+        }
+
+        /// Request data of the operation built from the examples.
+        fn example_request(&self, operation: &Value) -> Value {
+            if let Some(parameters) = operation["parameters"].as_array() {
+                let mut query = Map::new();
+                for parameter in parameters {
+                    if let Some(example) = parameter.get("example") {
+                        let name = parameter["name"].as_str().unwrap();
+                        query.insert(name.to_owned(), example.clone());
+                    }
+                }
+                return Value::Object(query);
+            }
+            operation["requestBody"]["content"]["application/json"]["example"].clone()
+        }
+
+        /// Handles the request and returns body of the response.
+        fn handle(&self, request: &ApiRequest) -> Result<String, String> {
+            let method = method(&request.kind);
+            let path = request.endpoint.strip_prefix(API_ENDPOINT).unwrap();
+            let operation = self.operation(method, path)?;
+
+            let parameters = operation["parameters"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let query = request.query.clone().unwrap_or_default();
+            for (name, value) in &query {
+                let parameter = parameters
+                    .iter()
+                    .find(|x| x["name"] == name.as_str())
+                    .ok_or(format!("query parameter {name} is not described"))?;
+                let value = match parameter["schema"]["type"].as_str() {
+                    Some("integer") => value.parse::<i64>().map(Value::from).unwrap_or_default(),
+                    Some("boolean") => value.parse::<bool>().map(Value::from).unwrap_or_default(),
+                    _ => Value::from(value.as_str()),
+                };
+                self.validate(&parameter["schema"], &value, name)?;
+            }
+            for parameter in &parameters {
+                let name = parameter["name"].as_str().unwrap();
+                if parameter["required"] == true && !query.iter().any(|x| x.0 == name) {
+                    return Err(format!("required query parameter {name} is missing"));
+                }
+            }
+
+            let content = &operation["requestBody"]["content"];
+            match (&request.body, &request.body_raw) {
+                (Some(body), _) => {
+                    let schema = content["application/json"]
+                        .get("schema")
+                        .ok_or("body is not expected")?;
+                    self.validate(schema, &serde_json::from_str(body).unwrap(), "body")?;
+                }
+                (None, Some(_)) => {
+                    let is_binary = content
+                        .as_object()
+                        .map(|x| x.values().any(|x| x["schema"]["format"] == "binary"))
+                        .unwrap_or(false);
+                    if !is_binary {
+                        return Err("binary body is not expected".to_owned());
+                    }
+                }
+                (None, None) if operation["requestBody"]["required"] == true => {
+                    return Err("body is missing".to_owned());
+                }
+                (None, None) => (),
+            };
+
+            let response = &operation["responses"]["200"]["content"]["application/json"];
+            match response.get("example") {
+                Some(example) => {
+                    self.validate(&response["schema"], example, "response")?;
+                    Ok(example.to_string())
+                }
+                None => Ok(String::new()),
+            }
+        }
+
+        fn validate(&self, schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+            if let Some(reference) = schema["$ref"].as_str() {
+                let name = reference.strip_prefix("#/components/schemas/").unwrap();
+                return self.validate(&self.spec["components"]["schemas"][name], value, at);
+            }
+            if value.is_null() {
+                return match schema["nullable"] == true {
+                    true => Ok(()),
+                    false => Err(format!("{at} is not nullable")),
+                };
+            }
+            if let Some(all_of) = schema["allOf"].as_array() {
+                return all_of.iter().try_for_each(|x| self.validate(x, value, at));
+            }
+            if let Some(values) = schema["enum"].as_array() {
+                if !values.contains(value) {
+                    return Err(format!("{at} is not one of {values:?}"));
+                }
+            }
+
+            let is_valid = match schema["type"].as_str() {
+                Some("object") => {
+                    let object = value.as_object().ok_or(format!("{at} is not an object"))?;
+                    for required in schema["required"].as_array().into_iter().flatten() {
+                        if !object.contains_key(required.as_str().unwrap()) {
+                            return Err(format!("{at}.{required} is missing"));
+                        }
+                    }
+                    for (name, field) in object {
+                        let field_schema = match schema["properties"].get(name) {
+                            Some(field_schema) => field_schema,
+                            None if schema["additionalProperties"].is_object() => {
+                                &schema["additionalProperties"]
+                            }
+                            None => return Err(format!("{at}.{name} is not described")),
+                        };
+                        self.validate(field_schema, field, &format!("{at}.{name}"))?;
+                    }
+                    true
+                }
+                Some("array") => {
+                    let array = value.as_array().ok_or(format!("{at} is not an array"))?;
+                    for (i, item) in array.iter().enumerate() {
+                        self.validate(&schema["items"], item, &format!("{at}[{i}]"))?;
+                    }
+                    true
+                }
+                Some("string") => value.is_string(),
+                Some("integer") => value.is_i64() || value.is_u64(),
+                Some("number") => value.is_number(),
+                Some("boolean") => value.is_boolean(),
+                _ => return Err(format!("{at} has unknown type")),
+            };
+            match is_valid {
+                true => Ok(()),
+                false => Err(format!("{at} is not {}", schema["type"])),
+            }
+        }
+
+        fn check<Req, Res>(
+            &self,
+            name: &str,
+            kind: ApiRequestKind,
+            path: &str,
+            raw: bool,
+        ) -> Result<(), String>
+        where
+            Req: Serialize + DeserializeOwned,
+            Res: Serialize + DeserializeOwned + 'static,
+        {
+            let method = method(&kind);
+            let operation = self.operation(method, path)?;
+            if operation["operationId"] != name {
+                return Err(format!("operationId is not {name}"));
+            }
+
+            let request = match raw {
+                true => TypedRequest::<Res>::new_raw(kind, path, b"\x89PNG".to_vec()),
+                false => {
+                    let example = self.example_request(operation);
+                    let data = serde_json::from_value::<Req>(example.clone())
+                        .map_err(|err| format!("request example does not match: {err}"))?;
+                    if serde_json::to_value(&data).unwrap() != example {
+                        return Err("request example is changed by the client".to_owned());
+                    }
+                    TypedRequest::<Res>::new(kind, path, &data)
+                }
+            };
+
+            let body = self.handle(&request.request)?;
+            let response = serde_json::to_value(TypedRequest::<Res>::parse(&body)).unwrap();
+            let expected = match body.is_empty() {
+                true => Value::Null,
+                false => serde_json::from_str(&body).unwrap(),
+            };
+            if response != expected {
+                return Err(format!("response is read as {response}"));
+            }
+            Ok(())
+        }
+    }
+
+    impl EndpointVisitor for MockServer {
+        fn visit<Req, Res>(&mut self, name: &str, kind: ApiRequestKind, path: &str, raw: bool)
+        where
+            Req: Serialize + DeserializeOwned,
+            Res: Serialize + DeserializeOwned + 'static,
+        {
+            let method = method(&kind);
+            if !self.visited.insert((method.to_owned(), path.to_owned())) {
+                self.errors
+                    .push(format!("{name}: {method} /{path} is declared twice"));
+            }
+            if let Err(err) = self.check::<Req, Res>(name, kind, path, raw) {
+                self.errors.push(format!("{name}: {err}"));
+            }
+        }
+    }
+
+    fn method(kind: &ApiRequestKind) -> &'static str {
+        match kind {
+            ApiRequestKind::Get => "get",
+            ApiRequestKind::Post => "post",
+            ApiRequestKind::Put => "put",
+            ApiRequestKind::Delete => "delete",
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn client_matches_openapi_description() {
+        let mut server = MockServer::new();
+        visit_endpoints(&mut server);
+        assert!(server.errors.is_empty(), "{:#?}", server.errors);
+    }
+
+    #[wasm_bindgen_test]
+    fn every_described_operation_has_client_function() {
+        let mut server = MockServer::new();
+        visit_endpoints(&mut server);
+
+        let paths = server.spec["paths"].as_object().unwrap();
+        for (path, operations) in paths {
+            for method in operations.as_object().unwrap().keys() {
+                let operation = (method.clone(), path.trim_start_matches('/').to_owned());
+                assert!(
+                    server.visited.contains(&operation),
+                    "{method} {path} has no function in the client"
+                );
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn mock_server_rejects_undescribed_fields() {
+        let server = MockServer::new();
+        let request = accounts::login(&accounts::LoginRequestData {
+            email: "harlequin@example.com".to_owned(),
+            password_hash: String::new(),
+            two_factor_code: None,
+        });
+        assert!(server.handle(&request.request).is_ok());
+
+        let request = TypedRequest::<NoContent>::new(
+            ApiRequestKind::Post,
+            "accounts/auth/login",
+            &serde_json::json!({
+                "email": "harlequin@example.com",
+                "passwordHash": "",
+                "twoFactorCode": null,
+                "rememberMe": true,
+            }),
+        );
+        assert_eq!(
+            server.handle(&request.request),
+            Err("body.rememberMe is not described".to_owned())
+        );
+
+        let request = TypedRequest::<NoContent>::new(
+            ApiRequestKind::Post,
+            "accounts/auth/login",
+            &serde_json::json!({ "email": "harlequin@example.com" }),
+        );
+        assert_eq!(
+            server.handle(&request.request),
+            Err("body.passwordHash is missing".to_owned())
+        );
+    }
+}
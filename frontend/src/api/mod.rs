@@ -1,20 +1,38 @@
 use arc_cell::ArcCell;
 use const_format::concatcp;
 use gloo_net::http::{Request, Response};
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{AbortController, AbortSignal};
 
-use crate::{app::App, app_status_bar::AppStatusBar, common::threading, helpers::prelude::*};
+use crate::{app_status_bar::AppStatusBar, common::threading, helpers::prelude::*};
+
+use self::refresh::{RefreshOutcome, SingleFlightRefresh};
+
+pub use self::abort::AbortHandle;
+
+pub mod client;
+pub mod query;
+
+mod abort;
+mod refresh;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 //pub const DOMAIN: &str = "http://localhost:9080";
 pub const DOMAIN: &str = "https://test-fsqa7u.noisestudio.net";
 const API_ENDPOINT: &str = concatcp!(DOMAIN, "/api/v1/");
+const DEFAULT_TIMEOUT: i32 = 30000;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const MAX_RETRY_AFTER: i32 = 60000;
 
 lazy_static! {
     static ref REFRESH_TOKEN: ArcCell<Uuid> = ArcCell::default();
     static ref REQUEST_LOCK: async_std::sync::RwLock<()> = async_std::sync::RwLock::new(());
+    static ref REFRESH: SingleFlightRefresh = SingleFlightRefresh::default();
 }
 
 #[derive(Serialize_repr, Deserialize_repr)]
@@ -29,6 +47,10 @@ pub struct ApiRequest {
     query: Option<Vec<(String, String)>>,
     body: Option<String>,
     body_raw: Option<Vec<u8>>,
+    timeout: i32,
+    /// `None` retries until the request succeeds or is aborted, see [`Self::retry_forever`].
+    max_retries: Option<u32>,
+    abort_signal: Option<AbortSignal>,
 }
 
 pub enum ApiResponse<T> {
@@ -48,6 +70,41 @@ pub struct ErrorData {
     pub errors: HashMap<String, ErrorDataElement>,
 }
 
+#[derive(Debug)]
+pub enum RequestError {
+    /// Request was cancelled by its [`AbortHandle`].
+    Aborted,
+    /// Server was not reachable or did not respond within the retry limit.
+    RetriesExhausted,
+    /// Session was revoked or its refresh token was rejected, the caller logs out.
+    Unauthorized,
+}
+
+impl RequestError {
+    /// Errors in the form of a rejected request, so callers report both of them the same way.
+    pub fn to_errors(&self) -> HashMap<String, ErrorDataElement> {
+        let translation_key = match self {
+            RequestError::Aborted => "requestAborted",
+            RequestError::RetriesExhausted => "requestFailed",
+            RequestError::Unauthorized => "requestUnauthorized",
+        };
+        HashMap::from([(
+            String::new(),
+            ErrorDataElement {
+                code: 0,
+                translation_key: translation_key.to_owned(),
+            },
+        )])
+    }
+}
+
+/// Result of a single attempt to send the request.
+enum AttemptError {
+    Aborted,
+    TimedOut,
+    Network,
+}
+
 enum ApiRequestKind {
     Get,
     Post,
@@ -67,22 +124,6 @@ struct RefreshTokenData {
     refresh_token: Uuid,
 }
 
-pub fn get(endpoint: &str) -> ApiRequest {
-    ApiRequest::new(ApiRequestKind::Get, endpoint)
-}
-
-pub fn post(endpoint: &str) -> ApiRequest {
-    ApiRequest::new(ApiRequestKind::Post, endpoint)
-}
-
-pub fn put(endpoint: &str) -> ApiRequest {
-    ApiRequest::new(ApiRequestKind::Put, endpoint)
-}
-
-pub fn delete(endpoint: &str) -> ApiRequest {
-    ApiRequest::new(ApiRequestKind::Delete, endpoint)
-}
-
 pub fn put_with_own(endpoint: &str) -> ApiRequest {
     ApiRequest::new_with_own(ApiRequestKind::Put, endpoint)
 }
@@ -119,6 +160,9 @@ impl ApiRequest {
             query: None,
             body: None,
             body_raw: None,
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: Some(DEFAULT_MAX_RETRIES),
+            abort_signal: None,
         }
     }
 
@@ -149,17 +193,52 @@ impl ApiRequest {
         self
     }
 
-    pub async fn send_async(&self) -> Response {
+    /// Time in milliseconds after which a single attempt is cancelled.
+    pub fn timeout(mut self, milliseconds: i32) -> Self {
+        self.timeout = milliseconds;
+        self
+    }
+
+    /// Number of retries after network errors, timeouts and server errors.
+    pub fn retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Retries until the request succeeds or is aborted. Only for background work of the
+    /// session, which has no component to report the failure to.
+    pub fn retry_forever(mut self) -> Self {
+        self.max_retries = None;
+        self
+    }
+
+    pub fn abort_with(mut self, handle: &AbortHandle) -> Self {
+        self.abort_signal = Some(handle.signal());
+        self
+    }
+
+    pub async fn try_send_async(&self) -> Result<Response, RequestError> {
+        let mut retries = 0;
         let mut wait_time = 0;
         loop {
             if wait_time != 0 {
+                if Some(retries) == self.max_retries {
+                    return Err(RequestError::RetriesExhausted);
+                }
+                retries += 1;
+
                 threading::sleep(wait_time).await;
                 wait_time = 0;
             }
+            if self.is_aborted() {
+                return Err(RequestError::Aborted);
+            }
 
+            let generation = REFRESH.generation();
             let response = match self.create_request_and_send_read_lock().await {
                 Ok(r) => r,
-                Err(_) => {
+                Err(AttemptError::Aborted) => return Err(RequestError::Aborted),
+                Err(AttemptError::TimedOut | AttemptError::Network) => {
                     AppStatusBar::set_connection(false);
                     wait_time = 3000;
                     continue;
@@ -169,20 +248,17 @@ impl ApiRequest {
             match response.status() {
                 200 | 400 => {
                     AppStatusBar::set_connection(true);
-                    return response;
+                    return Ok(response);
                 }
                 401 => {
                     AppStatusBar::set_connection(true);
                     match response.json::<UnauthorizedData>().await {
                         Ok(data) => match data.is_expired {
-                            true => {
-                                self.refresh_token().await;
-                                continue;
-                            }
-                            false => {
-                                App::logout_without_api();
-                                panic!("Logged out.");
-                            }
+                            true => match REFRESH.refresh(generation, refresh_token).await {
+                                RefreshOutcome::Refreshed => continue,
+                                RefreshOutcome::Failed => return Err(RequestError::Unauthorized),
+                            },
+                            false => return Err(RequestError::Unauthorized),
                         },
                         Err(_) => unreachable!(),
                     }
@@ -193,12 +269,17 @@ impl ApiRequest {
                 }
                 408 | 500 | 502 | 504 => {
                     AppStatusBar::set_connection(false);
-                    wait_time = 1000;
+                    wait_time = retry_after(&response).unwrap_or(1000);
+                    continue;
+                }
+                429 => {
+                    AppStatusBar::set_connection(true);
+                    wait_time = retry_after(&response).unwrap_or(5000);
                     continue;
                 }
                 503 => {
                     AppStatusBar::set_connection(false);
-                    wait_time = 5000;
+                    wait_time = retry_after(&response).unwrap_or(5000);
                     continue;
                 }
                 _ => panic!("Unable to send API request."),
@@ -206,37 +287,11 @@ impl ApiRequest {
         }
     }
 
-    pub fn send<F, T>(self, callback: F)
-    where
-        F: FnOnce(ApiResponse<T>) + 'static,
-        T: DeserializeOwned,
-    {
-        wasm_bindgen_futures::spawn_local(async move {
-            let response = self.send_async().await;
-            match response.status() {
-                200 => callback(ApiResponse::Ok(response.json::<T>().await.unwrap())),
-                400 => callback(ApiResponse::BadRequest(
-                    response.json::<ErrorData>().await.unwrap().errors,
-                )),
-                _ => unreachable!(),
-            };
-        });
-    }
-
-    pub fn send_without_ok<F>(self, callback: F)
-    where
-        F: FnOnce(ApiResponse<()>) + 'static,
-    {
-        wasm_bindgen_futures::spawn_local(async move {
-            let response = self.send_async().await;
-            match response.status() {
-                200 => callback(ApiResponse::Ok(())),
-                400 => callback(ApiResponse::BadRequest(
-                    response.json::<ErrorData>().await.unwrap().errors,
-                )),
-                _ => unreachable!(),
-            };
-        });
+    fn is_aborted(&self) -> bool {
+        self.abort_signal
+            .as_ref()
+            .map(|signal| signal.aborted())
+            .unwrap_or(false)
     }
 
     fn create_request(&self) -> Request {
@@ -266,69 +321,229 @@ impl ApiRequest {
             .header("Access-Control-Allow-Credentials", "true")
     }
 
-    async fn create_request_and_send_read_lock(&self) -> Result<Response, gloo_net::Error> {
-        let request = self.create_request();
+    async fn create_request_and_send_read_lock(&self) -> Result<Response, AttemptError> {
         let _lock = REQUEST_LOCK.read().await;
-        request.send().await
+        self.send_attempt().await
     }
 
-    async fn create_request_and_send_write_lock(&self) -> Result<Response, gloo_net::Error> {
-        let request = self.create_request();
+    async fn create_request_and_send_write_lock(&self) -> Result<Response, AttemptError> {
         let _lock = REQUEST_LOCK.write().await;
-        request.send().await
+        self.send_attempt().await
     }
 
-    async fn refresh_token(&self) {
-        let mut wait_time = 0;
-        loop {
-            if wait_time != 0 {
-                threading::sleep(wait_time).await;
+    /// Sends the request once. Own controller of the attempt is aborted by the timeout or by
+    /// the abort handle of the request.
+    async fn send_attempt(&self) -> Result<Response, AttemptError> {
+        #[cfg(test)]
+        if let Some(response) = test_support::send(self).await {
+            return Ok(response);
+        }
+
+        let controller = AbortController::new().expect("Unable to create AbortController.");
+        let abort: Closure<dyn FnMut()> = {
+            let controller = controller.clone();
+            Closure::new(move || controller.abort())
+        };
+
+        let window = WebPage::window();
+        let timeout = window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                abort.as_ref().unchecked_ref(),
+                self.timeout,
+            )
+            .unwrap();
+        if let Some(signal) = &self.abort_signal {
+            signal
+                .add_event_listener_with_callback("abort", abort.as_ref().unchecked_ref())
+                .unwrap();
+        }
+
+        let result = self
+            .create_request()
+            .abort_signal(Some(&controller.signal()))
+            .send()
+            .await;
+
+        window.clear_timeout_with_handle(timeout);
+        if let Some(signal) = &self.abort_signal {
+            signal
+                .remove_event_listener_with_callback("abort", abort.as_ref().unchecked_ref())
+                .unwrap();
+        }
+
+        match result {
+            Ok(response) => Ok(response),
+            Err(_) if self.is_aborted() => Err(AttemptError::Aborted),
+            Err(_) if controller.signal().aborted() => Err(AttemptError::TimedOut),
+            Err(_) => Err(AttemptError::Network),
+        }
+    }
+}
+
+/// Rotates the refresh token. Must be called only through [`SingleFlightRefresh`], because
+/// the server rejects already used tokens.
+async fn refresh_token() -> RefreshOutcome {
+    let mut wait_time = 0;
+    loop {
+        if wait_time != 0 {
+            threading::sleep(wait_time).await;
+        }
+
+        let response = match ApiRequest::new(ApiRequestKind::Post, "accounts/auth/refreshtoken")
+            .body(&RefreshTokenData {
+                refresh_token: *REFRESH_TOKEN.get(),
+            })
+            .create_request_and_send_write_lock()
+            .await
+        {
+            Ok(r) => r,
+            Err(_) => {
+                AppStatusBar::set_connection(false);
+                wait_time = 3000;
+                continue;
             }
+        };
 
-            let response = match post("accounts/auth/refreshtoken")
-                .body(&RefreshTokenData {
-                    refresh_token: *REFRESH_TOKEN.get(),
-                })
-                .create_request_and_send_write_lock()
-                .await
-            {
-                Ok(r) => r,
-                Err(_) => {
-                    AppStatusBar::set_connection(false);
-                    wait_time = 3000;
-                    continue;
-                }
-            };
+        match response.status() {
+            200 => {
+                AppStatusBar::set_connection(true);
+                set_refresh_token(
+                    response
+                        .json::<RefreshTokenData>()
+                        .await
+                        .unwrap()
+                        .refresh_token,
+                );
+                return RefreshOutcome::Refreshed;
+            }
+            400 | 401 => {
+                AppStatusBar::set_connection(true);
+                return RefreshOutcome::Failed;
+            }
+            408 | 500 | 502 | 504 => {
+                AppStatusBar::set_connection(false);
+                wait_time = 1000;
+                continue;
+            }
+            503 => {
+                AppStatusBar::set_connection(false);
+                wait_time = 5000;
+                continue;
+            }
+            _ => panic!("Unable to send API request."),
+        };
+    }
+}
 
-            match response.status() {
-                200 => {
-                    AppStatusBar::set_connection(true);
-                    set_refresh_token(
-                        response
-                            .json::<RefreshTokenData>()
-                            .await
-                            .unwrap()
-                            .refresh_token,
-                    );
-                    break;
-                }
-                400 | 401 => {
-                    AppStatusBar::set_connection(true);
-                    App::logout_without_api();
-                    return;
-                }
-                408 | 500 | 502 | 504 => {
-                    AppStatusBar::set_connection(false);
-                    wait_time = 1000;
-                    continue;
-                }
-                503 => {
-                    AppStatusBar::set_connection(false);
-                    wait_time = 5000;
-                    continue;
-                }
-                _ => panic!("Unable to send API request."),
-            };
+/// Delay in milliseconds requested by the server, either in seconds or as a HTTP date.
+fn retry_after(response: &Response) -> Option<i32> {
+    let value = response.headers().get("Retry-After")?;
+    let milliseconds = match value.trim().parse::<f64>() {
+        Ok(seconds) => seconds * 1000.0,
+        Err(_) => js_sys::Date::parse(&value) - js_sys::Date::now(),
+    };
+    match milliseconds.is_nan() {
+        true => None,
+        false => Some((milliseconds as i32).clamp(1, MAX_RETRY_AFTER)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use futures::future::join_all;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::test_support::{Calls, MockTransport};
+    use super::*;
+
+    /// Server whose access token is expired until the refresh token is rotated. Only the current
+    /// refresh token is accepted.
+    struct MockServer {
+        refresh_token: Cell<Uuid>,
+        is_expired: Cell<bool>,
+        reject_refresh: bool,
+        requests: Calls,
+        refreshes: Calls,
+    }
+
+    impl MockServer {
+        fn install(reject_refresh: bool) -> (Rc<Self>, MockTransport) {
+            let server = Rc::new(Self {
+                refresh_token: Cell::new(Uuid::new_v4()),
+                is_expired: Cell::new(true),
+                reject_refresh,
+                requests: Calls::default(),
+                refreshes: Calls::default(),
+            });
+            REFRESH_TOKEN.set(Arc::new(server.refresh_token.get()));
+
+            let handler = server.clone();
+            let transport = MockTransport::new(move |path, body| {
+                let server = handler.clone();
+                async move { server.handle(&path, body).await }
+            });
+            (server, transport)
         }
+
+        async fn handle(&self, path: &str, body: Option<String>) -> (u16, String) {
+            if path != "accounts/auth/refreshtoken" {
+                self.requests.receive().await;
+                return match self.is_expired.get() {
+                    true => (401, r#"{"isExpired":true}"#.to_owned()),
+                    false => (200, "{}".to_owned()),
+                };
+            }
+
+            self.refreshes.receive().await;
+            let data = serde_json::from_str::<RefreshTokenData>(&body.unwrap()).unwrap();
+            if self.reject_refresh || data.refresh_token != self.refresh_token.get() {
+                return (400, r#"{"errors":{}}"#.to_owned());
+            }
+            let refresh_token = Uuid::new_v4();
+            self.refresh_token.set(refresh_token);
+            self.is_expired.set(false);
+            (
+                200,
+                serde_json::to_string(&RefreshTokenData { refresh_token }).unwrap(),
+            )
+        }
+    }
+
+    fn requests() -> Vec<ApiRequest> {
+        (0..4)
+            .map(|_| ApiRequest::new(ApiRequestKind::Get, "accounts/getws"))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    async fn expired_requests_refresh_once_and_retry() {
+        let (server, _transport) = MockServer::install(false);
+
+        let requests = requests();
+        let responses = join_all(requests.iter().map(|x| x.try_send_async())).await;
+
+        // Requests which saw the token before it was refreshed retry without refreshing again.
+        assert!(responses
+            .iter()
+            .all(|x| matches!(x, Ok(response) if response.status() == 200)));
+        assert_eq!(server.refreshes.get(), 1);
+        assert_eq!(server.requests.get(), 8);
+        assert_eq!(*REFRESH_TOKEN.get(), server.refresh_token.get());
+    }
+
+    #[wasm_bindgen_test]
+    async fn rejected_refresh_makes_requests_unauthorized() {
+        let (server, _transport) = MockServer::install(true);
+
+        let requests = requests();
+        let responses = join_all(requests.iter().map(|x| x.try_send_async())).await;
+
+        assert!(responses
+            .iter()
+            .all(|x| matches!(x, Err(RequestError::Unauthorized))));
+        assert_eq!(server.refreshes.get(), 1);
+        assert_eq!(server.requests.get(), 4);
     }
 }
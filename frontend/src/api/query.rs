@@ -0,0 +1,409 @@
+//! Cache of responses to GET requests. Requests with the same key which are in flight at the same
+//! time are sent only once, fresh responses are reused without asking the API, and stale responses
+//! are returned immediately while they are reloaded in the background. Components subscribe to
+//! a path to be told when its cached response was invalidated or reloaded.
+
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use async_std::channel::{self, Receiver, Sender};
+use serde::de::DeserializeOwned;
+use yew::Callback;
+
+use crate::app::App;
+
+use super::{client::TypedRequest, ApiResponse, RequestError};
+
+type Value = Arc<dyn Any + Send + Sync>;
+
+lazy_static! {
+    static ref CACHE: QueryCache = QueryCache::default();
+}
+
+thread_local! {
+    static SUBSCRIBERS: RefCell<HashMap<u64, Subscriber>> = RefCell::new(HashMap::new());
+    static NEXT_SUBSCRIPTION_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+#[derive(Clone, Copy)]
+pub struct QueryOptions {
+    /// Milliseconds for which the response is returned without asking the API.
+    pub fresh_for: f64,
+    /// Milliseconds after the response stopped being fresh, for which it is still returned while
+    /// it is reloaded in the background.
+    pub stale_for: f64,
+}
+
+impl QueryOptions {
+    /// Only joins the request in flight, the response is not kept.
+    pub const DEDUPLICATE: Self = Self {
+        fresh_for: 0.0,
+        stale_for: 0.0,
+    };
+
+    fn keep_for(&self) -> f64 {
+        self.fresh_for + self.stale_for
+    }
+}
+
+/// Subscription to a path, which is cancelled when dropped.
+pub struct Subscription {
+    id: u64,
+}
+
+struct Subscriber {
+    path: String,
+    callback: Callback<()>,
+}
+
+#[derive(Default)]
+struct QueryCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Default)]
+struct Entry {
+    value: Option<Value>,
+    loaded_at: f64,
+    invalidated: bool,
+    /// Longest time for which any caller of the key wants to keep the value, it is dropped after
+    /// the request completes when no caller does.
+    keep_for: f64,
+    /// Senders of requests waiting for the response, `Some` while the request is in flight.
+    waiters: Option<Vec<Sender<Option<Value>>>>,
+}
+
+enum Action {
+    Return(Value),
+    Revalidate(Value),
+    Wait(Receiver<Option<Value>>),
+    Load,
+}
+
+/// Gets the response from the cache, or sends the request when it is not cached or is stale.
+/// Returns `None` when the request failed.
+pub async fn fetch<T>(request: TypedRequest<T>, options: QueryOptions) -> Option<Arc<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    fetch_worker(request, options, false).await
+}
+
+/// Same as [`fetch`], but ignores the cached response. Request already in flight is joined.
+pub async fn refetch<T>(request: TypedRequest<T>, options: QueryOptions) -> Option<Arc<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    fetch_worker(request, options, true).await
+}
+
+/// Marks cached responses of the path and its sub-paths as outdated, and tells subscribers of
+/// the path to fetch them again.
+pub fn invalidate(path: &str) {
+    CACHE.invalidate(path);
+    notify(path);
+}
+
+/// Removes all cached responses, used when the user logs out.
+pub fn clear() {
+    CACHE.entries.lock().unwrap().clear();
+}
+
+/// Calls the callback when responses of the path are invalidated or reloaded in the background.
+pub fn subscribe(path: &str, callback: Callback<()>) -> Subscription {
+    let id = NEXT_SUBSCRIPTION_ID.with(|x| x.replace(x.get() + 1));
+    let subscriber = Subscriber {
+        path: path.to_owned(),
+        callback,
+    };
+    SUBSCRIBERS.with(|x| x.borrow_mut().insert(id, subscriber));
+    Subscription { id }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        SUBSCRIBERS.with(|x| x.borrow_mut().remove(&self.id));
+    }
+}
+
+async fn fetch_worker<T>(
+    request: TypedRequest<T>,
+    options: QueryOptions,
+    force: bool,
+) -> Option<Arc<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let key = request.cache_key();
+    let value = CACHE
+        .fetch(key, options, force, move || load(request))
+        .await?;
+    Some(value.downcast::<T>().unwrap())
+}
+
+async fn load<T>(request: TypedRequest<T>) -> Option<Value>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    match request.try_send_async().await {
+        Ok(ApiResponse::Ok(value)) => Some(Arc::new(value)),
+        Ok(ApiResponse::BadRequest(errors)) => {
+            log::error!(
+                "Query was rejected: {:?}.",
+                errors.keys().collect::<Vec<_>>()
+            );
+            None
+        }
+        Err(RequestError::Aborted) => None,
+        Err(RequestError::Unauthorized) => {
+            App::logout_without_api();
+            None
+        }
+        Err(err) => {
+            log::error!("Unable to send API request: {err:?}.");
+            None
+        }
+    }
+}
+
+fn notify(path: &str) {
+    let callbacks = SUBSCRIBERS.with(|x| {
+        x.borrow()
+            .values()
+            .filter(|x| is_sub_path(&x.path, path) || is_sub_path(path, &x.path))
+            .map(|x| x.callback.clone())
+            .collect::<Vec<_>>()
+    });
+    for callback in callbacks {
+        callback.emit(());
+    }
+}
+
+/// Whether the key is the path itself, its query or its sub-path.
+fn is_sub_path(key: &str, path: &str) -> bool {
+    match key.strip_prefix(path) {
+        Some(rest) => matches!(rest.chars().next(), None | Some('?') | Some('/')),
+        None => false,
+    }
+}
+
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+impl QueryCache {
+    async fn fetch<F, Fut>(
+        &'static self,
+        key: String,
+        options: QueryOptions,
+        force: bool,
+        load: F,
+    ) -> Option<Value>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<Value>> + 'static,
+    {
+        match self.action(&key, options, force) {
+            Action::Return(value) => Some(value),
+            Action::Revalidate(value) => {
+                let future = load();
+                wasm_bindgen_futures::spawn_local(async move {
+                    if self.complete(&key, future.await).is_some() {
+                        notify(&key);
+                    }
+                });
+                Some(value)
+            }
+            Action::Wait(receiver) => receiver.recv().await.ok().flatten(),
+            Action::Load => {
+                let value = load().await;
+                self.complete(&key, value)
+            }
+        }
+    }
+
+    fn action(&self, key: &str, options: QueryOptions, force: bool) -> Action {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(key.to_owned()).or_default();
+        entry.keep_for = entry.keep_for.max(options.keep_for());
+
+        if let (Some(value), false) = (&entry.value, force || entry.invalidated) {
+            let age = now() - entry.loaded_at;
+            if age < options.fresh_for {
+                return Action::Return(value.clone());
+            }
+            if age < options.fresh_for + options.stale_for {
+                // Stale response is reloaded only once.
+                if entry.waiters.is_some() {
+                    return Action::Return(value.clone());
+                }
+                entry.waiters = Some(Vec::new());
+                return Action::Revalidate(value.clone());
+            }
+        }
+
+        match &mut entry.waiters {
+            Some(waiters) => {
+                let (sender, receiver) = channel::bounded(1);
+                waiters.push(sender);
+                Action::Wait(receiver)
+            }
+            None => {
+                entry.waiters = Some(Vec::new());
+                Action::Load
+            }
+        }
+    }
+
+    /// Stores the loaded value and passes it to the waiting requests.
+    fn complete(&self, key: &str, value: Option<Value>) -> Option<Value> {
+        let waiters = {
+            let mut entries = self.entries.lock().unwrap();
+            // Entry is missing when the cache was cleared while the request was in flight.
+            let entry = entries.get_mut(key)?;
+            let waiters = entry.waiters.take().unwrap_or_default();
+
+            if let Some(value) = &value {
+                entry.value = Some(value.clone());
+                entry.loaded_at = now();
+                entry.invalidated = false;
+            }
+            if entry.keep_for <= 0.0 || entry.value.is_none() {
+                entries.remove(key);
+            }
+            waiters
+        };
+
+        for waiter in waiters {
+            let _ = waiter.try_send(value.clone());
+        }
+        value
+    }
+
+    fn invalidate(&self, path: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        for (key, entry) in entries.iter_mut() {
+            if is_sub_path(key, path) {
+                entry.invalidated = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::{join, join_all};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::api::test_support::Calls;
+
+    const CACHED: QueryOptions = QueryOptions {
+        fresh_for: 60000.0,
+        stale_for: 0.0,
+    };
+    const STALE: QueryOptions = QueryOptions {
+        fresh_for: 0.0,
+        stale_for: 60000.0,
+    };
+
+    fn cache() -> &'static QueryCache {
+        Box::leak(Box::default())
+    }
+
+    /// Server which answers with the number of requests it received.
+    #[derive(Clone, Default)]
+    struct MockServer {
+        calls: Calls,
+    }
+
+    impl MockServer {
+        fn get(&self) -> impl Future<Output = Option<Value>> + 'static {
+            let calls = self.calls.clone();
+            async move {
+                calls.receive().await;
+                Some(Arc::new(calls.get()) as Value)
+            }
+        }
+    }
+
+    async fn fetch(
+        cache: &'static QueryCache,
+        server: &MockServer,
+        options: QueryOptions,
+    ) -> usize {
+        let value = cache
+            .fetch("users".to_owned(), options, false, || server.get())
+            .await;
+        *value.unwrap().downcast::<usize>().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn concurrent_requests_are_sent_once() {
+        let (cache, server) = (cache(), MockServer::default());
+
+        let values =
+            join_all((0..8).map(|_| fetch(cache, &server, QueryOptions::DEDUPLICATE))).await;
+
+        assert_eq!(server.calls.get(), 1);
+        assert!(values.iter().all(|x| *x == 1));
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn deduplicated_request_keeps_response_for_caching_caller() {
+        let (cache, server) = (cache(), MockServer::default());
+
+        let values = join(
+            fetch(cache, &server, QueryOptions::DEDUPLICATE),
+            fetch(cache, &server, CACHED),
+        )
+        .await;
+
+        assert_eq!(values, (1, 1));
+        assert_eq!(fetch(cache, &server, CACHED).await, 1);
+        assert_eq!(server.calls.get(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn fresh_response_is_reused_until_invalidated() {
+        let (cache, server) = (cache(), MockServer::default());
+
+        assert_eq!(fetch(cache, &server, CACHED).await, 1);
+        assert_eq!(fetch(cache, &server, CACHED).await, 1);
+        assert_eq!(server.calls.get(), 1);
+
+        cache.invalidate("users");
+        assert_eq!(fetch(cache, &server, CACHED).await, 2);
+        assert_eq!(server.calls.get(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    async fn stale_response_is_returned_while_reloading() {
+        let (cache, server) = (cache(), MockServer::default());
+
+        assert_eq!(fetch(cache, &server, STALE).await, 1);
+        assert_eq!(fetch(cache, &server, STALE).await, 1);
+        assert_eq!(fetch(cache, &server, STALE).await, 1);
+
+        // Let the reload in the background finish.
+        for _ in 0..8 {
+            async_std::task::yield_now().await;
+        }
+        assert_eq!(server.calls.get(), 2);
+        assert_eq!(fetch(cache, &server, CACHED).await, 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn sub_paths_are_matched_by_segments() {
+        assert!(is_sub_path("accounts/friends", "accounts/friends"));
+        assert!(is_sub_path("accounts/friends?id=5", "accounts/friends"));
+        assert!(is_sub_path("accounts/friends/mutual", "accounts/friends"));
+        assert!(!is_sub_path("accounts/friendrequests", "accounts/friends"));
+    }
+}
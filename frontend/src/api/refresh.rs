@@ -0,0 +1,152 @@
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use async_std::sync::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum RefreshOutcome {
+    #[default]
+    Refreshed,
+    Failed,
+}
+
+/// Makes sure only one refresh of the token is in flight. Every request remembers the generation
+/// of the token it was sent with. When the token was already refreshed after that, the request
+/// waits for the running refresh and retries with the new token instead of refreshing again.
+#[derive(Default)]
+pub struct SingleFlightRefresh {
+    generation: AtomicU64,
+    last_outcome: Mutex<RefreshOutcome>,
+}
+
+impl SingleFlightRefresh {
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub async fn refresh<F, Fut>(&self, observed_generation: u64, refresh: F) -> RefreshOutcome
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = RefreshOutcome>,
+    {
+        let mut last_outcome = self.last_outcome.lock().await;
+        if self.generation() != observed_generation {
+            return *last_outcome;
+        }
+
+        let outcome = refresh().await;
+        *last_outcome = outcome;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::future::join_all;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::api::test_support::Calls;
+
+    /// Server which rotates the refresh token and rejects already used tokens.
+    struct MockServer {
+        token: Cell<u32>,
+        calls: Calls,
+        reject: bool,
+    }
+
+    impl MockServer {
+        fn new(reject: bool) -> Self {
+            Self {
+                token: Cell::new(1),
+                calls: Calls::default(),
+                reject,
+            }
+        }
+
+        async fn post_refresh_token(&self, token: u32) -> (u16, u32) {
+            self.calls.receive().await;
+
+            if self.reject || token != self.token.get() {
+                return (400, 0);
+            }
+            self.token.set(token + 1);
+            (200, token + 1)
+        }
+    }
+
+    /// Client side of the mock, which keeps the current refresh token.
+    struct MockClient<'a> {
+        server: &'a MockServer,
+        token: Cell<u32>,
+        refresh: SingleFlightRefresh,
+    }
+
+    impl<'a> MockClient<'a> {
+        fn new(server: &'a MockServer) -> Self {
+            Self {
+                server,
+                token: Cell::new(1),
+                refresh: SingleFlightRefresh::default(),
+            }
+        }
+
+        async fn refresh(&self, observed_generation: u64) -> RefreshOutcome {
+            self.refresh
+                .refresh(observed_generation, || async {
+                    match self.server.post_refresh_token(self.token.get()).await {
+                        (200, token) => {
+                            self.token.set(token);
+                            RefreshOutcome::Refreshed
+                        }
+                        _ => RefreshOutcome::Failed,
+                    }
+                })
+                .await
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn concurrent_expired_requests_refresh_once() {
+        let server = MockServer::new(false);
+        let client = MockClient::new(&server);
+
+        let generation = client.refresh.generation();
+        let outcomes = join_all((0..8).map(|_| client.refresh(generation))).await;
+
+        assert!(outcomes.iter().all(|&x| x == RefreshOutcome::Refreshed));
+        assert_eq!(server.calls.get(), 1);
+        assert_eq!(client.token.get(), server.token.get());
+    }
+
+    #[wasm_bindgen_test]
+    async fn stale_caller_retries_without_refresh() {
+        let server = MockServer::new(false);
+        let client = MockClient::new(&server);
+
+        assert_eq!(client.refresh(0).await, RefreshOutcome::Refreshed);
+        assert_eq!(client.refresh(0).await, RefreshOutcome::Refreshed);
+        assert_eq!(server.calls.get(), 1);
+
+        // Request sent with the new token refreshes again after it expires.
+        assert_eq!(client.refresh(1).await, RefreshOutcome::Refreshed);
+        assert_eq!(server.calls.get(), 2);
+        assert_eq!(client.token.get(), 3);
+    }
+
+    #[wasm_bindgen_test]
+    async fn failed_refresh_is_shared_by_waiters() {
+        let server = MockServer::new(true);
+        let client = MockClient::new(&server);
+
+        let outcomes = join_all((0..4).map(|_| client.refresh(0))).await;
+
+        assert!(outcomes.iter().all(|&x| x == RefreshOutcome::Failed));
+        assert_eq!(server.calls.get(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Helpers shared by tests of the API.
+
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    rc::Rc,
+};
+
+use futures::{future::LocalBoxFuture, FutureExt};
+use gloo_net::http::Response;
+use web_sys::ResponseInit;
+
+use super::{ApiRequest, API_ENDPOINT};
+
+type Handler = Rc<dyn Fn(String, Option<String>) -> LocalBoxFuture<'static, (u16, String)>>;
+
+thread_local! {
+    static TRANSPORT: RefCell<Option<Handler>> = const { RefCell::new(None) };
+}
+
+/// Requests received by a mock server, clones share the count.
+#[derive(Clone, Default)]
+pub struct Calls(Rc<Cell<usize>>);
+
+impl Calls {
+    pub fn get(&self) -> usize {
+        self.0.get()
+    }
+
+    /// Records the request and lets other requests run while this one is in flight.
+    pub async fn receive(&self) {
+        self.0.set(self.0.get() + 1);
+        async_std::task::yield_now().await;
+    }
+}
+
+/// Answers requests of [`ApiRequest`] instead of the network until it is dropped. The handler
+/// receives the path of the endpoint with the body, and returns the status with the body.
+pub struct MockTransport;
+
+impl MockTransport {
+    pub fn new<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(String, Option<String>) -> Fut + 'static,
+        Fut: Future<Output = (u16, String)> + 'static,
+    {
+        let handler: Handler = Rc::new(move |path, body| handler(path, body).boxed_local());
+        TRANSPORT.with(|x| *x.borrow_mut() = Some(handler));
+        Self
+    }
+}
+
+impl Drop for MockTransport {
+    fn drop(&mut self) {
+        TRANSPORT.with(|x| *x.borrow_mut() = None);
+    }
+}
+
+/// Response of the installed mock transport, `None` when there is no mock.
+pub(super) async fn send(request: &ApiRequest) -> Option<Response> {
+    let handler = TRANSPORT.with(|x| x.borrow().clone())?;
+    let path = request.endpoint.trim_start_matches(API_ENDPOINT).to_owned();
+    let (status, body) = handler(path, request.body.clone()).await;
+
+    let init = ResponseInit::new();
+    init.set_status(status);
+    let response = web_sys::Response::new_with_opt_str_and_init(Some(&body), &init).unwrap();
+    Some(Response::from_raw(response))
+}
@@ -1,93 +1,97 @@
-use std::sync::{Arc, Mutex};
-
-use arc_cell::ArcCell;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
 use crate::{
-    account::{friends_views::friends::Friends, login::Login},
-    api::{self, ApiResponse},
+    account::{
+        blocked_users,
+        friends_views::{friend_requests, friends::Friends},
+        login::Login,
+        password_reset::PasswordReset,
+        profile_popover::ProfilePopover,
+        registration::Registration,
+    },
+    api::{self, client::accounts, ApiResponse},
     app_me::AppMe,
     app_status_bar::AppStatusBar,
     channel_views::channel::Channel,
-    common::UnsafeSync,
     direct_messages_views::{direct_channels::DirectChannels, encryption},
     helpers::prelude::WebPage,
     localization,
     navigator::Navigator,
-    notifier,
-    route::{self, Route},
+    notifier, presence,
+    route::{self, FriendsTab, Route},
+    search_views::search_index,
     settings_views::settings::Settings,
+    store::{
+        self, channels,
+        session::{Session, SessionAction},
+        users::Users,
+    },
 };
 
-lazy_static! {
-    static ref INSTANCE: ArcCell<Option<Mutex<UnsafeSync<Callback<Msg>>>>> = ArcCell::default();
-    static ref USER_ID: ArcCell<i64> = ArcCell::default();
+pub struct App {}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub route: Route,
 }
 
-pub struct App {
-    logged_in: bool,
-    is_settings_displayed: bool,
-    openned_channel: i64,
+/// View displayed while the user is not logged in.
+#[derive(Clone, Copy)]
+pub enum LoginView {
+    Login,
+    Registration,
+    PasswordReset,
 }
 
 pub enum Msg {
     Login(i64),
-    Logout,
-    DisplaySettings(bool),
-    OpennedChannel(i64),
+    DisplayLoginView(LoginView),
 }
 
 impl Component for App {
     type Message = Msg;
-    type Properties = ();
+    type Properties = Props;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let s = Self {
-            logged_in: false,
-            is_settings_displayed: false,
-            openned_channel: 0,
-        };
-        INSTANCE.set(Arc::new(Some(Mutex::new(
-            ctx.link().callback(|m| m).into(),
-        ))));
+        store::dispatch(SessionAction::Navigate(ctx.props().route.clone()));
 
         let user_id = WebPage::local_storage().get_item("user_id").unwrap();
-        if let Some(user_id) = user_id {
-            let user_id = user_id.parse::<i64>().unwrap();
-            if api::try_load() && encryption::try_load() {
-                ctx.link().callback(move |_| Msg::Login(user_id)).emit(());
-                return s;
+        match user_id {
+            Some(user_id) if api::try_load() && encryption::try_load() => {
+                Self::login(user_id.parse::<i64>().unwrap());
             }
+            _ => Self::remove_session(),
         }
 
-        Self::remove_session();
-        s
+        Self::redirect(&ctx.props().route);
+        Self {}
     }
 
-    fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Login(user_id) => {
-                self.logged_in = true;
-
-                USER_ID.set(Arc::new(user_id));
-                WebPage::local_storage()
-                    .set_item("user_id", &user_id.to_string())
-                    .expect("Unable to set user_id to session storage.");
-
-                notifier::connect();
+                Self::login(user_id);
+                Self::redirect(&ctx.props().route);
             }
-            Msg::Logout => {
-                self.logged_in = false;
-                self.is_settings_displayed = false;
-                self.openned_channel = 0;
+            Msg::DisplayLoginView(login_view) => {
+                route::navigate(match login_view {
+                    LoginView::Login => Route::Login,
+                    LoginView::Registration => Route::Register,
+                    LoginView::PasswordReset => Route::PasswordReset,
+                });
+                return false;
             }
-            Msg::DisplaySettings(display) => self.is_settings_displayed = display,
-            Msg::OpennedChannel(openned_channel) => self.openned_channel = openned_channel,
         };
         true
     }
 
+    fn changed(&mut self, ctx: &Context<Self>, _: &Self::Properties) -> bool {
+        store::dispatch(SessionAction::Navigate(ctx.props().route.clone()));
+        Self::redirect(&ctx.props().route);
+        true
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
         html! {
             <div class="app-container">
@@ -100,17 +104,24 @@ impl Component for App {
 
 impl App {
     pub fn user_id() -> i64 {
-        *USER_ID.get()
+        store::get::<Session>().user_id
     }
 
+    /// Opens settings, or returns to the channel which was opened before them.
     pub fn display_settings(display: bool) {
-        if let Some(instance) = INSTANCE.get().as_ref() {
-            instance.lock().unwrap().emit(Msg::DisplaySettings(display));
-        }
+        route::navigate(match display {
+            true => Route::Settings,
+            false => Route::channel(store::get::<Session>().opened_channel),
+        });
+    }
+
+    /// Opens the direct channel, or friends when the id is zero.
+    pub fn open_channel(channel_id: i64) {
+        route::navigate(Route::channel(channel_id));
     }
 
     pub fn logout() {
-        api::get("accounts/auth/logout").send_without_ok(move |r: ApiResponse<()>| match r {
+        accounts::logout().send(move |r| match r {
             ApiResponse::Ok(_) => (),
             ApiResponse::BadRequest(_) => {
                 log::error!("Failed to logout.");
@@ -121,8 +132,33 @@ impl App {
 
     pub(crate) fn logout_without_api() {
         Self::remove_session();
-        if let Some(instance) = INSTANCE.get().as_ref() {
-            instance.lock().unwrap().emit(Msg::Logout);
+        store::dispatch(SessionAction::Logout);
+        route::redirect(Route::Login);
+    }
+
+    fn login(user_id: i64) {
+        store::dispatch(SessionAction::Login(user_id));
+        WebPage::local_storage()
+            .set_item("user_id", &user_id.to_string())
+            .expect("Unable to set user_id to session storage.");
+
+        blocked_users::load();
+        friend_requests::load();
+        notifier::connect();
+        presence::start();
+    }
+
+    /// Keeps routes which require login away from users who are not logged in, and login views
+    /// away from users who are.
+    fn redirect(route: &Route) {
+        let is_logged_in = store::get::<Session>().is_logged_in();
+        match route {
+            Route::NotFound if !is_logged_in => route::redirect(Route::Login),
+            route if !is_logged_in && route.requires_login() => route::redirect_to_login(route),
+            route if is_logged_in && !route.requires_login() => {
+                route::redirect(route::login_redirect().unwrap_or(Route::Home))
+            }
+            _ => (),
         }
     }
 
@@ -131,12 +167,25 @@ impl App {
         storage.remove_item("user_id").unwrap();
         storage.remove_item("refresh_token").unwrap();
         storage.remove_item("encryption_block_hash").unwrap();
+        search_index::clear();
+        api::query::clear();
+        blocked_users::clear();
+        friend_requests::clear();
+        store::reset::<channels::DirectChannels>();
+        store::reset::<Users>();
+        presence::stop();
     }
 
     fn element_view(&self, ctx: &Context<Self>) -> Html {
         let app_callback = ctx.link().callback(|m| m);
-        if !self.logged_in {
-            return html! { <Login {app_callback} /> };
+        let route = &ctx.props().route;
+        let session = store::get::<Session>();
+        if !session.is_logged_in() {
+            return match route {
+                Route::Register => html! { <Registration {app_callback} /> },
+                Route::PasswordReset => html! { <PasswordReset {app_callback} /> },
+                _ => html! { <Login {app_callback} /> },
+            };
         }
 
         let styles = html! { <>
@@ -146,17 +195,18 @@ impl App {
             <link rel="stylesheet" href="/static/css/settings_views/settings.css" />
         </> };
 
-        if self.is_settings_displayed {
+        if let Some(page) = route.settings_page() {
             return html! { <>
                 {styles}
-                <Settings />
+                <Settings {page} />
             </> };
         }
 
-        let content = if self.openned_channel == 0 {
-            html! { <Friends app_callback={app_callback.clone()} /> }
+        let content = if session.opened_channel == 0 {
+            let tab = route.friends_tab().unwrap_or(FriendsTab::Online);
+            html! { <Friends {tab} /> }
         } else {
-            html! { <Channel channel_id={self.openned_channel} /> }
+            html! { <Channel channel_id={session.opened_channel} /> }
         };
 
         html! {
@@ -165,11 +215,11 @@ impl App {
 
                 <div class="app">
                     <div class="app-inner">
-                        <Navigator app_callback={app_callback.clone()} />
+                        <Navigator />
                         <div class="app-tree-navigator">
                             <div class="app-tree-navigator-content">
                                 <div class="app-tree-navigator-content-inner">
-                                    <DirectChannels app_callback={app_callback} />
+                                    <DirectChannels />
                                 </div>
                             </div>
 
@@ -180,6 +230,7 @@ impl App {
                         </div>
                     </div>
                 </div>
+                <ProfilePopover />
             </>
         }
     }
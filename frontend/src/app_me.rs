@@ -2,22 +2,40 @@ use yew::prelude::*;
 use yew_icons::{Icon, IconId};
 
 use crate::{
-    account::load_user::{LoadUser, LoadUserContext},
+    account::user_views::UserBadge,
     app::App,
+    helpers::prelude::*,
+    localization,
+    presence::{self, Presence},
 };
 
+const MAX_CUSTOM_STATUS_LENGTH: usize = 128;
+
+#[derive(Properties, PartialEq, Clone)]
+struct StatusPickerProps {
+    close: Callback<()>,
+}
+
 #[function_component(AppMe)]
 pub fn app_me() -> Html {
+    let is_picker_displayed = use_state(|| false);
+
+    let toggle_picker = {
+        let is_picker_displayed = is_picker_displayed.clone();
+        Callback::from(move |_| is_picker_displayed.set(!*is_picker_displayed))
+    };
+    let close_picker = {
+        let is_picker_displayed = is_picker_displayed.clone();
+        Callback::from(move |_| is_picker_displayed.set(false))
+    };
+
     html! {
         <div class="noselect app-me-container">
-            <div class="app-me-profile user-profile-container">
-                <LoadUser<()>
-                    props={()}
-                    user_id={App::user_id()}
-                    view={Callback::from(process_user_view)}
-                    with_status={true}
-                    refresh={true}
-                />
+            if *is_picker_displayed {
+                <StatusPicker close={close_picker} />
+            }
+            <div class="app-me-profile user-profile-container" onclick={toggle_picker}>
+                <UserBadge user_id={App::user_id()} with_status={true} />
             </div>
             <div class="app-me-buttons">
                 <Icon onclick={Callback::from(|_| App::display_settings(true))} icon_id={IconId::FontAwesomeSolidGear}/>
@@ -26,19 +44,100 @@ pub fn app_me() -> Html {
     }
 }
 
-fn process_user_view(ctx: LoadUserContext<()>) -> Html {
-    if ctx.user.is_none() {
-        return html! { {"Loading..."} };
-    }
-    let user = ctx.user.unwrap();
+#[function_component(StatusPicker)]
+fn status_picker(props: &StatusPickerProps) -> Html {
+    let lang = localization::get_language();
+    let current = presence::presence();
+
+    let options = [
+        (
+            presence::STATUS_ONLINE,
+            "viewAppMeStatusOnline",
+            "user-status-online",
+        ),
+        (
+            presence::STATUS_IDLE,
+            "viewAppMeStatusIdle",
+            "user-status-idle",
+        ),
+        (
+            presence::STATUS_DO_NOT_DISTURB,
+            "viewAppMeStatusDoNotDisturb",
+            "user-status-donotdisturb",
+        ),
+        (
+            presence::STATUS_OFFLINE,
+            "viewAppMeStatusInvisible",
+            "user-status-offline",
+        ),
+    ]
+    .into_iter()
+    .map(|(status, translation_key, status_class)| {
+        let close = props.close.clone();
+        let custom_text = current.custom_text.clone();
+        let onclick = Callback::from(move |_| {
+            presence::set_presence(Presence {
+                status,
+                custom_text: custom_text.clone(),
+            });
+            close.emit(());
+        });
+
+        let class = match current.status == status {
+            true => "app-me-status-option button-selected",
+            false => "app-me-status-option",
+        };
+        html! {
+            <div {class} {onclick}>
+                <div class={format!("user-status {status_class}")}></div>
+                <span>{lang.get(translation_key)}</span>
+            </div>
+        }
+    })
+    .collect::<Html>();
+
+    let save = {
+        let close = props.close.clone();
+        let status = current.status;
+        Callback::from(move |_| {
+            let custom_text = Input::by_id("app-me-custom-status")
+                .value()
+                .trim()
+                .chars()
+                .take(MAX_CUSTOM_STATUS_LENGTH)
+                .collect::<String>();
+            presence::set_presence(Presence {
+                status,
+                custom_text,
+            });
+            close.emit(());
+        })
+    };
+    let clear = {
+        let close = props.close.clone();
+        let status = current.status;
+        Callback::from(move |_| {
+            presence::set_presence(Presence {
+                status,
+                custom_text: String::new(),
+            });
+            close.emit(());
+        })
+    };
 
     html! {
-        <div class="user-profile">
-            <img class="user-avatar" src={user.avatar_url.clone()} alt={"avatar"} />
-            {user.status.as_ref().unwrap().icon_html()}
-            <div class="user-content">
-                <p class="app-me-user-name user-name">{user.name.clone()}</p>
-                <p class="app-me-user-info user-info">{"@"}{user.username.clone()}</p>
+        <div class="app-me-status-picker">
+            {options}
+            <div class="app-me-custom-status">
+                <input
+                    id="app-me-custom-status"
+                    type="text"
+                    maxlength={MAX_CUSTOM_STATUS_LENGTH.to_string()}
+                    placeholder={lang.get("viewAppMeCustomStatus")}
+                    value={current.custom_text.clone()}
+                />
+                <button onclick={save}>{lang.get("viewAppMeCustomStatusSave")}</button>
+                <button onclick={clear}>{lang.get("viewAppMeCustomStatusClear")}</button>
             </div>
         </div>
     }
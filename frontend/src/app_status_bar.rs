@@ -1,20 +1,14 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
-};
-
-use arc_cell::ArcCell;
 use yew::prelude::*;
 
-use crate::{common::UnsafeSync, localization};
+use crate::{
+    localization,
+    store::{self, connection::Connection},
+};
 
-lazy_static! {
-    static ref INSTANCE: ArcCell<Option<Mutex<UnsafeSync<Callback<Msg>>>>> = ArcCell::default();
-    static ref IS_CONNECTED: AtomicBool = AtomicBool::new(true);
+pub struct AppStatusBar {
+    _subscription: store::Subscription,
 }
 
-pub struct AppStatusBar {}
-
 pub enum Msg {
     UpdateConnection,
 }
@@ -24,11 +18,11 @@ impl Component for AppStatusBar {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let s = Self {};
-        INSTANCE.set(Arc::new(Some(Mutex::new(
-            ctx.link().callback(|m| m).into(),
-        ))));
-        s
+        Self {
+            _subscription: store::subscribe::<Connection>(
+                ctx.link().callback(|_| Msg::UpdateConnection),
+            ),
+        }
     }
 
     fn update(&mut self, _: &Context<Self>, _: Self::Message) -> bool {
@@ -36,7 +30,7 @@ impl Component for AppStatusBar {
     }
 
     fn view(&self, _: &Context<Self>) -> Html {
-        if IS_CONNECTED.load(Ordering::Relaxed) {
+        if store::get::<Connection>().is_connected {
             return html! {};
         }
 
@@ -51,12 +45,9 @@ impl Component for AppStatusBar {
 
 impl AppStatusBar {
     pub fn set_connection(is_connected: bool) {
-        if IS_CONNECTED.swap(is_connected, Ordering::Relaxed) == is_connected {
+        if store::get::<Connection>().is_connected == is_connected {
             return;
         }
-
-        if let Some(instance) = INSTANCE.get().as_ref() {
-            instance.lock().unwrap().emit(Msg::UpdateConnection);
-        }
+        store::dispatch(move |state: &mut Connection| state.is_connected = is_connected);
     }
 }
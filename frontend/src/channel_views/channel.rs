@@ -2,25 +2,33 @@ use std::sync::Arc;
 
 use base64::{engine::general_purpose, Engine as _};
 use gloo_timers::callback::Timeout;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 use web_sys::FileList;
 use yew::prelude::*;
 
 use crate::{
-    api,
+    account::user_views::UserName,
+    api::{
+        self,
+        client::attachments::{self, CreateBucketRequestData},
+        ApiResponse, RequestError,
+    },
     app::App,
     channel_views::channel_content::ChannelContent,
     direct_messages_views::encryption,
     helpers::prelude::*,
     localization, navigator,
-    route::{self, Route},
+    search_views::{search_index, search_panel::SearchPanel},
+    store::{self, channels::DirectChannels},
 };
 
-use super::{channel_content, channel_message::ChannelMessage};
+use super::{
+    channel_content, channel_message::ChannelMessage, channel_notifications::ChannelNotifications,
+};
 
 pub struct Channel {
     sent_message_id: i64,
+    replying_to: Option<i64>,
+    is_search_displayed: bool,
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -32,15 +40,9 @@ pub enum Msg {
     Refresh,
     ChangeChannel,
     Send,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct CreateBucketResponseData {
-    attachment_id: i64,
-    name: String,
-    storage_domain: String,
-    token: String,
+    Reply(i64),
+    CancelReply,
+    ToggleSearch,
 }
 
 struct FileSenderBucket {
@@ -62,7 +64,11 @@ impl Component for Channel {
     type Properties = Props;
 
     fn create(_: &Context<Self>) -> Self {
-        Self { sent_message_id: 0 }
+        Self {
+            sent_message_id: 0,
+            replying_to: None,
+            is_search_displayed: false,
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
@@ -70,22 +76,59 @@ impl Component for Channel {
             Msg::Refresh => (),
             Msg::ChangeChannel => (),
             Msg::Send => self.send_message(ctx),
+            Msg::Reply(message_id) => {
+                self.replying_to = Some(message_id);
+                Input::by_id("message").focus().unwrap();
+            }
+            Msg::CancelReply => self.replying_to = None,
+            Msg::ToggleSearch => self.is_search_displayed = !self.is_search_displayed,
         };
         true
     }
 
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().channel_id != old_props.channel_id {
+            self.replying_to = None;
+        }
+        true
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
         let lang = localization::get_language();
+
+        let reply = match self.replying_to {
+            Some(_) => html! {
+                <div class="channel-reply-bar">
+                    <span>{lang.get("viewChannelReplyingTo")}</span>
+                    <button onclick={ctx.link().callback(|_| Msg::CancelReply)}>{
+                        lang.get("viewChannelReplyCancel")
+                    }</button>
+                </div>
+            },
+            None => html! {},
+        };
+
+        let search = match self.is_search_displayed {
+            true => html! { <SearchPanel channel_id={ctx.props().channel_id} /> },
+            false => html! {},
+        };
+
         html! {
             <div class="channel-container">
-                <route::Router route={Route::Direct { id: ctx.props().channel_id }} />
 
-                <div>
-                    <h2>{"Channel name"}</h2>
+                <div class="channel-header">
+                    <ChannelTitle channel_id={ctx.props().channel_id} />
+                    <ChannelNotifications channel_id={ctx.props().channel_id} />
+                    <button onclick={ctx.link().callback(|_| Msg::ToggleSearch)}>{lang.get("viewSearchTitle")}</button>
                 </div>
+                {search}
 
-                <ChannelContent channel_id={ctx.props().channel_id} />
+                <ChannelContent
+                    channel_id={ctx.props().channel_id}
+                    reply_callback={ctx.link().callback(Msg::Reply)}
+                />
 
+                {reply}
                 <div class="channel-send-button-container">
                     <input type="file" id="message-file" multiple=true />
                     <input type="text" name="message" id="message" />
@@ -99,7 +142,7 @@ impl Component for Channel {
 impl Channel {
     fn send_message(&mut self, ctx: &Context<Self>) {
         let input = Input::by_id("message");
-        let message_content = input.value();
+        let message_content = ChannelMessage::with_reply(input.value(), self.replying_to.take());
 
         let channel_id = ctx.props().channel_id;
         self.sent_message_id -= 1;
@@ -132,18 +175,17 @@ impl Channel {
                 let message_id =
                     encryption::send_message(channel_id, message_content.clone()).await;
 
-                channel_content::edit_message(
-                    channel_id,
-                    sent_message_id,
-                    ChannelMessage::new(message_id, App::user_id(), Ok(Arc::new(message_content))),
-                );
+                let message =
+                    ChannelMessage::new(message_id, App::user_id(), Ok(Arc::new(message_content)));
+                search_index::index_message(channel_id, &message, 0);
+                channel_content::edit_message(channel_id, sent_message_id, message);
 
                 navigator::add_pings(channel_id, i64::MIN, message_id);
             });
         })
         .forget();
 
-        channel_content::set_scroll(channel_id, 0);
+        channel_content::jump_to_present(channel_id);
     }
 
     fn send_files(message_content: String, sent_message_id: i64, channel_id: i64, files: FileList) {
@@ -181,19 +223,18 @@ impl Channel {
 
         let mut body = File::to_bytes_without_exif(file).await;
 
-        let mut response = api::put("attachments/direct/bucket")
-            .body(&json!({
-                "size": body.len() as i64,
-                "name":  file.name(),
-                "alternateTextNonce": general_purpose::STANDARD.encode(nonce),
-                "encryptedAlternateText": general_purpose::STANDARD.encode("")
-            }))
-            .send_async()
-            .await;
-        let bucket = match response.status() {
-            200 => response.json::<CreateBucketResponseData>().await.unwrap(),
-            400 => todo!(),
-            _ => unreachable!(),
+        let bucket = match attachments::create_direct_bucket(&CreateBucketRequestData {
+            size: body.len() as i64,
+            name: file.name(),
+            alternate_text_nonce: general_purpose::STANDARD.encode(nonce),
+            encrypted_alternate_text: general_purpose::STANDARD.encode(""),
+        })
+        .retry_forever()
+        .send_async()
+        .await
+        {
+            ApiResponse::Ok(bucket) => bucket,
+            ApiResponse::BadRequest(_) => todo!(),
         };
 
         WebPage::crypto()
@@ -202,14 +243,16 @@ impl Channel {
 
         encryption::encrypt_aes(&aes, &nonce, &mut body).await;
 
-        response = api::put_with_own(&format!("{}/attachments", bucket.storage_domain))
+        let response = api::put_with_own(&format!("{}/attachments", bucket.storage_domain))
             .query([("token", bucket.token)])
             .body_raw(body)
-            .send_async()
+            .retry_forever()
+            .try_send_async()
             .await;
-        match response.status() {
-            200 => (),
-            400 => todo!(),
+        match response.map(|x| x.status()) {
+            Ok(200) => (),
+            Ok(400) => todo!(),
+            Err(RequestError::Unauthorized) => return App::logout_without_api(),
             _ => unreachable!(),
         };
 
@@ -247,3 +290,21 @@ impl FileSenderBucket {
         Channel::send_message_worker(message_content, self.sent_message_id, self.channel_id);
     }
 }
+
+/// Name of the user of the direct channel, which opens their profile.
+#[function_component(ChannelTitle)]
+fn channel_title(props: &Props) -> Html {
+    let channel_id = props.channel_id;
+    let user_id = store::use_selector(
+        move |x: &DirectChannels| x.find(channel_id).map(|x| x.user_id),
+        channel_id,
+    );
+
+    html! {
+        <h2>
+            if let Some(user_id) = user_id {
+                <UserName {user_id} with_profile={true} />
+            }
+        </h2>
+    }
+}
@@ -0,0 +1,427 @@
+use std::{
+    cell::{Ref, RefCell},
+    collections::{HashMap, VecDeque},
+};
+
+use crate::direct_messages_views::encryption;
+
+use super::channel_message::ChannelMessage;
+
+pub const PAGE_SIZE: usize = 50;
+pub const ESTIMATED_MESSAGE_HEIGHT: i32 = 40;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum PageRequest {
+    Latest,
+    Before(i64),
+    After(i64),
+    Around(i64),
+}
+
+pub enum LoadedPage {
+    Latest(Vec<ChannelMessage>),
+    Before(i64, Vec<ChannelMessage>),
+    After(i64, Vec<ChannelMessage>),
+    Around(i64, Vec<ChannelMessage>, Vec<ChannelMessage>),
+}
+
+/// Loaded messages of one channel. Messages are stored in segments of contiguous history,
+/// sorted from the oldest one, and there is an unknown gap between every two segments.
+pub(crate) struct ChannelCache {
+    pub segments: Vec<MessageSegment>,
+    pub current: usize,
+    pub scroll_y: i32,
+    heights: HashMap<i64, i32>,
+    /// Offsets of the current segment, with index of the segment for which they were computed.
+    offsets: RefCell<Option<(usize, Vec<i32>)>>,
+}
+
+pub(crate) struct MessageSegment {
+    pub messages: VecDeque<(i64, ChannelMessage)>,
+    pub is_beginning: bool,
+    pub is_present: bool,
+}
+
+impl PageRequest {
+    pub async fn fetch(self, channel_id: i64) -> LoadedPage {
+        match self {
+            PageRequest::Latest => {
+                LoadedPage::Latest(encryption::get_messages(channel_id, 0).await)
+            }
+            PageRequest::Before(before) => {
+                LoadedPage::Before(before, encryption::get_messages(channel_id, before).await)
+            }
+            PageRequest::After(after) => LoadedPage::After(
+                after,
+                encryption::get_messages_after(channel_id, after).await,
+            ),
+            PageRequest::Around(message_id) => {
+                // Before is exclusive, so the message itself is loaded together with older ones.
+                let older = encryption::get_messages(channel_id, message_id + 1).await;
+                let newer = encryption::get_messages_after(channel_id, message_id).await;
+                LoadedPage::Around(message_id, older, newer)
+            }
+        }
+    }
+}
+
+impl ChannelCache {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            current: 0,
+            scroll_y: 0,
+            heights: HashMap::new(),
+            offsets: RefCell::new(None),
+        }
+    }
+
+    /// Rendered height of the message, or estimation when it was not rendered yet.
+    pub fn height(&self, message_id: i64) -> i32 {
+        *self
+            .heights
+            .get(&message_id)
+            .unwrap_or(&ESTIMATED_MESSAGE_HEIGHT)
+    }
+
+    pub fn set_height(&mut self, message_id: i64, height: i32) {
+        if self.heights.insert(message_id, height) != Some(height) {
+            self.invalidate_offsets();
+        }
+    }
+
+    /// Distances of the messages of the current segment from its bottom. Offset at index `i` is
+    /// the height of messages from `i` to the end, followed by zero for the end itself, so the
+    /// offsets never increase.
+    pub fn bottom_offsets(&self) -> Ref<'_, [i32]> {
+        let is_valid =
+            matches!(&*self.offsets.borrow(), Some((index, _)) if *index == self.current);
+        if !is_valid {
+            let mut offsets = vec![0];
+            if let Some(segment) = self.current() {
+                offsets.resize(segment.messages.len() + 1, 0);
+                for (i, message) in segment.messages.iter().enumerate().rev() {
+                    offsets[i] = offsets[i + 1] + self.height(message.0);
+                }
+            }
+            *self.offsets.borrow_mut() = Some((self.current, offsets));
+        }
+
+        Ref::map(self.offsets.borrow(), |x| x.as_ref().unwrap().1.as_slice())
+    }
+
+    fn invalidate_offsets(&mut self) {
+        *self.offsets.get_mut() = None;
+    }
+
+    pub fn current(&self) -> Option<&MessageSegment> {
+        self.segments.get(self.current)
+    }
+
+    pub fn find_segment(&self, message_id: i64) -> Option<usize> {
+        self.segments
+            .iter()
+            .position(|x| x.messages.iter().any(|m| m.0 == message_id))
+    }
+
+    pub fn find_message(&self, message_id: i64) -> Option<&ChannelMessage> {
+        self.segments
+            .iter()
+            .flat_map(|x| x.messages.iter())
+            .find(|x| x.0 == message_id)
+            .map(|x| &x.1)
+    }
+
+    pub fn present(&self) -> Option<usize> {
+        self.segments.iter().position(|x| x.is_present)
+    }
+
+    pub fn select_present(&mut self) -> bool {
+        match self.present() {
+            Some(index) => {
+                self.current = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Appends live message to the newest known part of the history.
+    pub fn push(&mut self, message: ChannelMessage) {
+        if self.find_segment(message.message_id).is_some() {
+            return;
+        }
+        self.invalidate_offsets();
+
+        let element = (message.message_id, message);
+        match self.present() {
+            Some(index) => self.segments[index].messages.push_back(element),
+            None => self.segments.push(MessageSegment {
+                messages: VecDeque::from([element]),
+                is_beginning: false,
+                is_present: true,
+            }),
+        }
+    }
+
+    pub fn edit(&mut self, message_id: i64, message: ChannelMessage) {
+        let new_id = message.message_id;
+        let exists = message_id != new_id && self.find_segment(new_id).is_some();
+        self.invalidate_offsets();
+
+        for segment in self.segments.iter_mut() {
+            if let Some(index) = segment.messages.iter().position(|x| x.0 == message_id) {
+                match exists {
+                    true => {
+                        segment.messages.remove(index);
+                    }
+                    false => segment.messages[index] = (new_id, message),
+                };
+                break;
+            }
+        }
+    }
+
+    /// Inserts loaded page and returns index of the segment which contains it.
+    pub fn apply(&mut self, page: LoadedPage) -> usize {
+        let anchor = self.anchor();
+        self.invalidate_offsets();
+
+        let index = match page {
+            LoadedPage::Latest(messages) => {
+                let is_beginning = messages.len() < PAGE_SIZE;
+                let index = self.insert_segment(MessageSegment {
+                    messages: Self::sorted(messages),
+                    is_beginning,
+                    is_present: true,
+                });
+                self.merge(index)
+            }
+            LoadedPage::Before(before, messages) => {
+                let index = match self.find_segment(before) {
+                    Some(index) => index,
+                    None => return self.current,
+                };
+
+                let segment = &mut self.segments[index];
+                segment.is_beginning = messages.len() < PAGE_SIZE;
+                for message in Self::sorted(messages).into_iter().rev() {
+                    if message.0 < before {
+                        segment.messages.push_front(message);
+                    }
+                }
+
+                self.merge(index)
+            }
+            LoadedPage::After(after, messages) => {
+                let index = match self.find_segment(after) {
+                    Some(index) => index,
+                    None => return self.current,
+                };
+
+                let is_present = messages.len() < PAGE_SIZE;
+                let segment = &mut self.segments[index];
+
+                // Messages which are still being sent must stay at the end.
+                let mut sending = Vec::new();
+                while matches!(segment.messages.back(), Some(x) if x.0 <= 0) {
+                    sending.push(segment.messages.pop_back().unwrap());
+                }
+                segment
+                    .messages
+                    .extend(Self::sorted(messages).into_iter().filter(|x| x.0 > after));
+                segment.messages.extend(sending.into_iter().rev());
+                segment.is_present |= is_present;
+
+                self.merge(index)
+            }
+            LoadedPage::Around(message_id, older, newer) => {
+                let is_beginning = older.len() < PAGE_SIZE;
+                let is_present = newer.len() < PAGE_SIZE;
+
+                let mut messages = older;
+                messages.extend(newer);
+
+                let index = self.insert_segment(MessageSegment {
+                    messages: Self::sorted(messages),
+                    is_beginning,
+                    is_present,
+                });
+                let index = self.merge(index);
+                self.find_segment(message_id).unwrap_or(index)
+            }
+        };
+
+        self.current = anchor
+            .and_then(|anchor| self.find_segment(anchor))
+            .unwrap_or(index);
+        index
+    }
+
+    fn anchor(&self) -> Option<i64> {
+        self.current()
+            .and_then(|x| x.messages.iter().find(|m| m.0 > 0))
+            .map(|x| x.0)
+    }
+
+    fn insert_segment(&mut self, segment: MessageSegment) -> usize {
+        let index = match segment.is_present {
+            true => self.segments.len(),
+            false => {
+                let first = segment.first_id();
+                self.segments
+                    .iter()
+                    .position(|x| x.is_present || x.first_id() > first)
+                    .unwrap_or(self.segments.len())
+            }
+        };
+
+        self.segments.insert(index, segment);
+        index
+    }
+
+    /// Merges overlapping neighbours of the given segment and returns its new index.
+    fn merge(&mut self, mut index: usize) -> usize {
+        while index > 0 && self.segments[index - 1].overlaps(&self.segments[index]) {
+            let newer = self.segments.remove(index);
+            index -= 1;
+            self.segments[index].merge_newer(newer);
+        }
+
+        while index + 1 < self.segments.len()
+            && self.segments[index].overlaps(&self.segments[index + 1])
+        {
+            let newer = self.segments.remove(index + 1);
+            self.segments[index].merge_newer(newer);
+        }
+
+        index
+    }
+
+    fn sorted(messages: Vec<ChannelMessage>) -> VecDeque<(i64, ChannelMessage)> {
+        let mut messages = messages
+            .into_iter()
+            .map(|x| (x.message_id, x))
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|x| x.0);
+        messages.dedup_by_key(|x| x.0);
+        messages.into()
+    }
+}
+
+impl MessageSegment {
+    /// Id of the oldest message. Messages which are still being sent have negative ids.
+    pub fn first_id(&self) -> i64 {
+        self.messages
+            .iter()
+            .find(|x| x.0 > 0)
+            .map_or(i64::MAX, |x| x.0)
+    }
+
+    pub fn last_id(&self) -> i64 {
+        self.messages
+            .iter()
+            .rev()
+            .find(|x| x.0 > 0)
+            .map_or(0, |x| x.0)
+    }
+
+    fn overlaps(&self, newer: &MessageSegment) -> bool {
+        (self.is_present && newer.is_present) || self.last_id() >= newer.first_id()
+    }
+
+    fn merge_newer(&mut self, newer: MessageSegment) {
+        let (mut messages, mut sending): (Vec<_>, Vec<_>) = self
+            .messages
+            .drain(..)
+            .chain(newer.messages)
+            .partition(|x| x.0 > 0);
+
+        messages.sort_by_key(|x| x.0);
+        messages.dedup_by_key(|x| x.0);
+        messages.append(&mut sending);
+
+        self.messages = messages.into();
+        self.is_beginning |= newer.is_beginning;
+        self.is_present |= newer.is_present;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    fn messages(ids: impl IntoIterator<Item = i64>) -> Vec<ChannelMessage> {
+        ids.into_iter()
+            .map(|id| ChannelMessage::new(id, 1, Ok(Arc::new(id.to_string()))))
+            .collect()
+    }
+
+    fn ids(cache: &ChannelCache) -> Vec<Vec<i64>> {
+        cache
+            .segments
+            .iter()
+            .map(|x| x.messages.iter().map(|m| m.0).collect())
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn before_page_joins_older_segment_and_keeps_current() {
+        let mut cache = ChannelCache::new();
+        cache.apply(LoadedPage::Latest(messages(101..=150)));
+        cache.apply(LoadedPage::Around(40, messages(1..=40), messages(41..=90)));
+        assert_eq!(
+            ids(&cache),
+            [(1..=90).collect(), (101..=150).collect::<Vec<_>>()]
+        );
+        assert_eq!(cache.current, 1);
+
+        cache.apply(LoadedPage::Before(101, messages(51..=100)));
+        assert_eq!(ids(&cache), [(1..=150).collect::<Vec<_>>()]);
+        assert!(cache.segments[0].is_beginning && cache.segments[0].is_present);
+        assert_eq!(cache.current, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn after_page_joins_newer_segment() {
+        let mut cache = ChannelCache::new();
+        cache.apply(LoadedPage::Latest(messages(101..=150)));
+        let index = cache.apply(LoadedPage::Around(20, messages(1..=20), messages(21..=70)));
+        assert_eq!(index, 0);
+
+        cache.apply(LoadedPage::After(70, messages(71..=120)));
+        assert_eq!(ids(&cache), [(1..=150).collect::<Vec<_>>()]);
+        assert!(cache.segments[0].is_beginning && cache.segments[0].is_present);
+    }
+
+    #[wasm_bindgen_test]
+    fn sending_messages_stay_at_end() {
+        let mut cache = ChannelCache::new();
+        cache.apply(LoadedPage::Latest(messages(1..=10)));
+        cache.push(messages([-1]).remove(0));
+
+        cache.apply(LoadedPage::After(10, messages(9..=12)));
+        assert_eq!(
+            ids(&cache),
+            [vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, -1]]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn around_page_merges_with_present_without_duplicates() {
+        let mut cache = ChannelCache::new();
+        cache.apply(LoadedPage::Latest(messages(41..=90)));
+        cache.push(messages([-1]).remove(0));
+
+        let index = cache.apply(LoadedPage::Around(30, messages(1..=30), messages(31..=50)));
+        let mut expected = (1..=90).collect::<Vec<_>>();
+        expected.push(-1);
+        assert_eq!(ids(&cache), [expected]);
+        assert_eq!(index, 0);
+        assert_eq!(cache.current, 0);
+    }
+}
@@ -1,116 +1,163 @@
-use std::{
-    num::NonZeroUsize,
-    sync::{Arc, Mutex},
-};
+use std::{cell::RefCell, collections::HashSet, num::NonZeroUsize, rc::Rc, sync::Arc};
 
 use arc_cell::ArcCell;
 use gloo_timers::callback::Timeout;
 use lru::LruCache;
-use serde_json::json;
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
 use crate::{
-    account::load_user::{LoadUser, LoadUserContext},
-    api::{self, ApiResponse},
-    common::UnsafeSync,
-    direct_messages_views::encryption,
+    account::{
+        blocked_users,
+        load_user::{use_user, UserState},
+        user_views::{UserAvatar, UserName},
+    },
+    api::{
+        client::channels::{self, MessagesAckRequestData},
+        ApiResponse,
+    },
+    app::App,
     helpers::prelude::*,
-    navigator,
+    localization, navigator, notifications,
+    store::{self, channels::DirectChannels, session::Session},
+};
+
+use super::{
+    channel_cache::{
+        ChannelCache, LoadedPage, MessageSegment, PageRequest, ESTIMATED_MESSAGE_HEIGHT,
+    },
+    channel_message::ChannelMessage,
 };
 
-use super::channel_message::ChannelMessage;
+/// Count of messages rendered above and below the visible area.
+const RENDER_BUFFER: usize = 20;
+
+thread_local! {
+    static CACHED_CHANNELS: RefCell<LruCache<i64, Rc<RefCell<ChannelCache>>>> =
+        RefCell::new(LruCache::new(NonZeroUsize::new(64).unwrap()));
+}
 
 lazy_static! {
-    static ref OPENED_CHANNEL: ArcCell<Option<(i64, UnsafeSync<Callback<Msg>>)>> =
-        ArcCell::default();
-    static ref CACHED_CHANNELS: Mutex<LruCache<i64, Arc<Mutex<ChannelCache>>>> =
-        Mutex::new(LruCache::new(NonZeroUsize::new(64).unwrap()));
+    static ref PENDING_JUMP: ArcCell<Option<(i64, i64)>> = ArcCell::default();
 }
 
-pub fn notify_message(channel_id: i64, message: ChannelMessage) {
-    if let Some(lock) = CACHED_CHANNELS.lock().unwrap().get(&channel_id) {
-        let mut lock = lock.lock().unwrap();
-        for m in lock.messages.iter_mut() {
-            if m.1.message_id == message.message_id {
-                return;
-            }
-        }
+/// Event for the displayed content of the channel, zero channel id is for any channel.
+#[derive(Clone, Copy)]
+pub struct ChannelEvent {
+    channel_id: i64,
+    kind: ChannelEventKind,
+}
 
-        lock.messages.push((message.message_id, message));
+#[derive(Clone, Copy)]
+enum ChannelEventKind {
+    Refresh,
+    PendingJump,
+    JumpToPresent,
+}
+
+pub fn notify_message(channel_id: i64, message: ChannelMessage) {
+    let is_mention = notifications::is_mention(&message);
+    if let Some(cache) = cached_channel(channel_id) {
+        cache.borrow_mut().push(message);
     }
 
     if !refresh_channel(channel_id) || !WebPage::is_focused() {
         navigator::add_pings(channel_id, 1, 0);
+        if is_mention {
+            navigator::add_mention(channel_id);
+        }
     } else {
         navigator::update_activity(channel_id);
     }
 }
 
 pub fn edit_message(channel_id: i64, message_id: i64, message: ChannelMessage) {
-    if let Some(cache) = CACHED_CHANNELS.lock().unwrap().get(&channel_id) {
-        let mut lock = cache.lock().unwrap();
-
-        let id = message.message_id;
-        for i in 0..lock.messages.len() {
-            if lock.messages[i].1.message_id == message_id {
-                lock.messages[i].1 = message;
-                break;
-            }
-        }
-
-        if message_id != id {
-            lock.messages
-                .dedup_by(|a, b| a.1.message_id == b.1.message_id);
-        }
+    if let Some(cache) = cached_channel(channel_id) {
+        cache.borrow_mut().edit(message_id, message);
     }
     refresh_channel(channel_id);
 }
 
-pub fn set_scroll(channel_id: i64, scroll: i32) {
-    if let Some(cache) = CACHED_CHANNELS.lock().unwrap().get(&channel_id) {
-        let mut lock = cache.lock().unwrap();
-        lock.scroll_y = scroll;
-    }
-    refresh_channel(channel_id);
+pub fn jump_to(channel_id: i64, message_id: i64) {
+    // Channel which is not displayed yet picks up the jump when it gets displayed.
+    PENDING_JUMP.set(Arc::new(Some((channel_id, message_id))));
+    emit(channel_id, ChannelEventKind::PendingJump);
+}
+
+pub fn jump_to_present(channel_id: i64) {
+    emit(channel_id, ChannelEventKind::JumpToPresent);
+}
+
+/// Rerenders the opened channel.
+pub fn refresh() {
+    emit(0, ChannelEventKind::Refresh);
 }
 
+/// Rerenders the channel, returns whether it is displayed.
 fn refresh_channel(channel_id: i64) -> bool {
-    let opened_channel = OPENED_CHANNEL.get();
-    if let Some((id, callback)) = opened_channel.as_ref() {
-        if channel_id == *id {
-            callback.0.emit(Msg::Refresh);
-            return true;
-        }
+    let session = store::get::<Session>();
+    if session.opened_channel != channel_id || session.is_settings_displayed {
+        return false;
     }
-    false
+
+    emit(channel_id, ChannelEventKind::Refresh);
+    true
+}
+
+fn emit(channel_id: i64, kind: ChannelEventKind) {
+    store::emit(ChannelEvent { channel_id, kind });
+}
+
+fn cached_channel(channel_id: i64) -> Option<Rc<RefCell<ChannelCache>>> {
+    CACHED_CHANNELS.with(|channels| channels.borrow_mut().get(&channel_id).cloned())
 }
 
 pub struct ChannelContent {
-    cache: Option<Arc<Mutex<ChannelCache>>>,
+    cache: Option<Rc<RefCell<ChannelCache>>>,
     scroll_event: Closure<dyn FnMut()>,
     latest_before: i64,
+    latest_after: i64,
+    pending_jump: Option<(i64, bool)>,
+    jump_to: Option<i64>,
+    highlighted: Option<i64>,
+    keep_scroll_top: Option<i32>,
+    client_height: i32,
+    focus_event: Closure<dyn FnMut()>,
+    last_seen: i64,
+    unread_marker: Option<i64>,
+    revealed_blocked: HashSet<i64>,
+    /// Error of the last jump, displayed above the messages.
+    status: Html,
+    _subscription: store::Subscription,
 }
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
     pub channel_id: i64,
+    pub reply_callback: Callback<i64>,
 }
 
 pub enum Msg {
     Refresh,
     Reload,
-    Load(Vec<ChannelMessage>),
+    Load(LoadedPage),
     ChangeChannel,
     SetScroll(i32),
     LoadUp,
+    LoadDown,
+    JumpTo(i64),
+    JumpToPresent,
+    Event(ChannelEvent),
+    RevealBlocked(i64),
 }
 
-struct ChannelCache {
-    messages: Vec<(i64, ChannelMessage)>,
-    is_scrolled_to_top: bool,
-    scroll_y: i32,
+#[derive(Properties, Clone, PartialEq)]
+struct MessageViewProps {
+    message: ChannelMessage,
+    parent: Option<ChannelMessage>,
+    callback: Callback<Msg>,
+    reply_callback: Callback<i64>,
 }
 
 impl Component for ChannelContent {
@@ -121,19 +168,39 @@ impl Component for ChannelContent {
         let callback = ctx.link().callback(|m| m);
         let scroll_event = Closure::new(move || {
             let scroll = Element::by_id("channel-content-scroll");
-            callback.emit(Msg::SetScroll(
-                scroll.scroll_height() - scroll.scroll_top() - scroll.client_height(),
-            ));
+            let scroll_y = scroll.scroll_height() - scroll.scroll_top() - scroll.client_height();
+            callback.emit(Msg::SetScroll(scroll_y));
 
             if scroll.scroll_top() < 500 {
                 callback.emit(Msg::LoadUp);
             }
+            if scroll_y < 500 {
+                callback.emit(Msg::LoadDown);
+            }
         });
 
+        let callback = ctx.link().callback(|m| m);
+        let focus_event = Closure::new(move || callback.emit(Msg::Refresh));
+        WebPage::window()
+            .add_event_listener_with_callback("focus", focus_event.as_ref().unchecked_ref())
+            .unwrap();
+
         let mut s = Self {
             cache: None,
             scroll_event,
             latest_before: 0,
+            latest_after: 0,
+            pending_jump: None,
+            jump_to: None,
+            highlighted: None,
+            keep_scroll_top: None,
+            client_height: 0,
+            focus_event,
+            last_seen: 0,
+            unread_marker: None,
+            revealed_blocked: HashSet::new(),
+            status: Html::default(),
+            _subscription: store::listen(ctx.link().callback(Msg::Event)),
         };
         s.change_channel(ctx);
         s
@@ -143,16 +210,37 @@ impl Component for ChannelContent {
         match msg {
             Msg::Refresh => (),
             Msg::Reload => {
-                self.load(ctx, 0);
+                self.load(ctx, PageRequest::Latest);
                 return false;
             }
-            Msg::Load(messages) => self.load_set(ctx, messages),
+            Msg::Load(page) => self.load_set(ctx, page),
             Msg::ChangeChannel => self.change_channel(ctx),
             Msg::SetScroll(scroll) => self.set_scroll(ctx, scroll),
             Msg::LoadUp => {
                 self.load_up(ctx);
                 return false;
             }
+            Msg::LoadDown => {
+                self.load_down(ctx);
+                return false;
+            }
+            Msg::JumpTo(message_id) => self.jump(ctx, message_id, true),
+            Msg::JumpToPresent => self.jump_to_present(ctx),
+            Msg::Event(event) => {
+                if event.channel_id != 0 && event.channel_id != ctx.props().channel_id {
+                    return false;
+                }
+                match event.kind {
+                    ChannelEventKind::Refresh => (),
+                    ChannelEventKind::PendingJump => {
+                        self.take_pending_jump(ctx);
+                    }
+                    ChannelEventKind::JumpToPresent => self.jump_to_present(ctx),
+                }
+            }
+            Msg::RevealBlocked(message_id) => {
+                self.revealed_blocked.insert(message_id);
+            }
         };
         true
     }
@@ -164,198 +252,588 @@ impl Component for ChannelContent {
         false
     }
 
-    fn view(&self, _: &Context<Self>) -> Html {
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+        let callback = ctx.link().callback(|m| m);
+        let reply_callback = ctx.props().reply_callback.clone();
+
         let content = match &self.cache {
             Some(arc) => {
-                let cache = arc.lock().unwrap();
-                let mut vec = Vec::with_capacity(cache.messages.len() + 1);
-
-                if cache.is_scrolled_to_top {
+                let cache = arc.borrow();
+                let segment = match cache.current() {
+                    Some(segment) => segment,
+                    None => return Self::content_view(html! { <p>{"Loading..."}</p> }, html! {}),
+                };
+
+                let (start, end) = self.visible_range(&cache, segment);
+                let (top_height, bottom_height) = {
+                    let offsets = cache.bottom_offsets();
+                    (offsets[0] - offsets[start], offsets[end])
+                };
+                let mut vec = Vec::with_capacity(end - start + 3);
+
+                if start == 0 && segment.is_beginning {
                     vec.push(html! {
                         <h2>{"This is the beginning of the chat"}</h2>
                     });
                 }
 
+                vec.push(html! {
+                    <div class="channel-content-spacer" style={format!("height: {}px", top_height)} />
+                });
+
                 let mut last_author = 0;
                 let mut count = 0;
 
-                for message in cache.messages.iter() {
-                    let html = if last_author == message.1.author_user_id && count < 10 {
-                        count += 1;
+                for (i, message) in segment.messages.iter().enumerate().take(end) {
+                    let message_id = message.0;
+                    let is_hidden = blocked_users::is_blocked(message.1.author_user_id)
+                        && !self.revealed_blocked.contains(&message_id);
+                    let with_avatar = !(last_author == message.1.author_user_id
+                        && count < 10
+                        && message.1.reply_to.is_none());
+                    match (is_hidden, with_avatar) {
+                        (true, _) => last_author = 0,
+                        (false, true) => {
+                            last_author = message.1.author_user_id;
+                            count = 1;
+                        }
+                        (false, false) => count += 1,
+                    };
+
+                    if i < start {
+                        continue;
+                    }
+
+                    if let Some(marker) = self.unread_marker {
+                        let is_first_unread = message_id > marker
+                            && match i {
+                                0 => segment.is_beginning,
+                                _ => segment.messages[i - 1].0 <= marker,
+                            };
+                        if is_first_unread {
+                            vec.push(html! {
+                                <div class="channel-unread-separator noselect">
+                                    <span>{lang.get("viewChannelNewMessages")}</span>
+                                </div>
+                            });
+                        }
+                    }
+
+                    let html = if is_hidden {
+                        html! {
+                            <div class="channel-message-blocked noselect">
+                                <span>{lang.get("viewChannelBlockedMessage")}</span>
+                                <button onclick={ctx.link().callback(move |_| Msg::RevealBlocked(message_id))}>{
+                                    lang.get("viewChannelBlockedMessageShow")
+                                }</button>
+                            </div>
+                        }
+                    } else if !with_avatar {
+                        let reply_callback = reply_callback.clone();
                         html! {
                             <div class="channel-message">
                                 <label class="message-without-avatar">{message.1.get_html().clone()}</label>
+                                <div class="channel-message-actions">
+                                    <button onclick={Callback::from(move |_| reply_callback.emit(message_id))}>{
+                                        lang.get("viewChannelReply")
+                                    }</button>
+                                </div>
                             </div>
                         }
                     } else {
-                        last_author = message.1.author_user_id;
-                        count = 1;
+                        let parent = message
+                            .1
+                            .reply_to
+                            .and_then(|parent_id| cache.find_message(parent_id).cloned());
                         html! {
-                            <LoadUser<ChannelMessage>
-                                props={message.1.clone()}
-                                user_id={message.1.author_user_id}
-                                view={Callback::from(process_message_view)}
-                                with_status={false}
-                                refresh={false}
+                            <MessageView
+                                message={message.1.clone()}
+                                {parent}
+                                callback={callback.clone()}
+                                reply_callback={reply_callback.clone()}
                             />
                         }
                     };
+
+                    let class = match self.highlighted == Some(message_id) {
+                        true => "channel-message-row channel-message-highlighted",
+                        false => "channel-message-row",
+                    };
                     vec.push(html! {
-                        <div key={message.0}>
+                        <div key={message_id} id={format!("channel-message-{}", message_id)} class={class}>
                             {html}
                         </div>
                     })
                 }
 
-                vec
+                vec.push(html! {
+                    <div class="channel-content-spacer" style={format!("height: {}px", bottom_height)} />
+                });
+
+                let banner = html! { <>
+                    {self.status.clone()}
+                    {self.banner_view(ctx, &cache, segment)}
+                </> };
+                return Self::content_view(html! { <>{vec}</> }, banner);
             }
-            None => vec![html! { <p>{"Loading..."}</p> }],
+            None => html! { <p>{"Loading..."}</p> },
         };
 
-        html! {
-            <div class="channel-content" id="channel-content-scroll">
-                <div class="channel-content-inner">
-                    {content}
-                </div>
-            </div>
-        }
+        Self::content_view(content, html! {})
     }
 
-    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
         let scroll = Element::by_id("channel-content-scroll");
         scroll
             .add_event_listener_with_callback("scroll", self.scroll_event.as_ref().unchecked_ref())
             .unwrap();
+        self.client_height = scroll.client_height();
 
         if let Some(cache) = &self.cache {
-            let lock = cache.lock().unwrap();
-            scroll.set_scroll_top(scroll.scroll_height() - lock.scroll_y - scroll.client_height());
+            let cache = cache.clone();
+            let mut lock = cache.borrow_mut();
+            self.measure(&mut lock);
+
+            // Newer messages were appended below, so the position from the top must be kept.
+            match self.keep_scroll_top.take() {
+                Some(scroll_top) => {
+                    scroll.set_scroll_top(scroll_top);
+                    lock.scroll_y =
+                        scroll.scroll_height() - scroll.scroll_top() - scroll.client_height();
+                }
+                None => scroll.set_scroll_top(
+                    scroll.scroll_height() - lock.scroll_y - scroll.client_height(),
+                ),
+            };
+
+            if let Some(message_id) = self.jump_to.take() {
+                let element = WebPage::document()
+                    .get_element_by_id(&format!("channel-message-{}", message_id));
+                if let Some(element) = element {
+                    element.scroll_into_view_with_bool(true);
+                    lock.scroll_y =
+                        scroll.scroll_height() - scroll.scroll_top() - scroll.client_height();
+                }
+            }
+
+            self.mark_seen(ctx, &lock);
         }
     }
+
+    fn destroy(&mut self, _: &Context<Self>) {
+        WebPage::window()
+            .remove_event_listener_with_callback("focus", self.focus_event.as_ref().unchecked_ref())
+            .unwrap();
+    }
 }
 
 impl ChannelContent {
-    fn load(&self, ctx: &Context<Self>, before: i64) {
+    /// Range of message indexes which should be rendered, derived from the distance to the bottom
+    /// so it stays stable when older messages are prepended.
+    fn visible_range(&self, cache: &ChannelCache, segment: &MessageSegment) -> (usize, usize) {
+        let len = segment.messages.len();
+        let client_height = match self.client_height {
+            0 => 1000,
+            client_height => client_height,
+        };
+
+        let jump_index = self
+            .jump_to
+            .and_then(|message_id| segment.messages.iter().position(|x| x.0 == message_id));
+        if let Some(index) = jump_index {
+            let visible = (client_height / ESTIMATED_MESSAGE_HEIGHT) as usize;
+            return (
+                index.saturating_sub(RENDER_BUFFER),
+                (index + visible + RENDER_BUFFER).min(len),
+            );
+        }
+
+        let end = Self::bottom_index(cache);
+        // Lowest message which fills the client height together with the messages below it.
+        let offsets = cache.bottom_offsets();
+        let top = offsets[end] + client_height;
+        let start = offsets.partition_point(|&x| x >= top).saturating_sub(1);
+
+        (
+            start.saturating_sub(RENDER_BUFFER),
+            (end + RENDER_BUFFER).min(len),
+        )
+    }
+
+    /// Index after the lowest message which is at least partially visible.
+    fn bottom_index(cache: &ChannelCache) -> usize {
+        let offsets = cache.bottom_offsets();
+        let len = offsets.len() - 1;
+        offsets[..len].partition_point(|&x| x > cache.scroll_y)
+    }
+
+    fn content_view(content: Html, banner: Html) -> Html {
+        html! {
+            <div class="channel-content-container">
+                <div class="channel-content" id="channel-content-scroll">
+                    <div class="channel-content-inner">
+                        {content}
+                    </div>
+                </div>
+                {banner}
+            </div>
+        }
+    }
+
+    fn banner_view(
+        &self,
+        ctx: &Context<Self>,
+        cache: &ChannelCache,
+        segment: &MessageSegment,
+    ) -> Html {
+        let lang = localization::get_language();
+        let unseen = self.unseen_count(cache);
+
+        let text = if unseen > 0 {
+            lang.get("viewChannelNewMessagesBanner")
+                .replace("{count}", &unseen.to_string())
+        } else if !segment.is_present {
+            lang.get("viewChannelJumpToPresent")
+        } else {
+            return html! {};
+        };
+
+        html! {
+            <div class="channel-new-messages-banner noselect" onclick={ctx.link().callback(|_| Msg::JumpToPresent)}>
+                {text}
+            </div>
+        }
+    }
+
+    /// Count of messages from other users which arrived below the seen part of the channel.
+    fn unseen_count(&self, cache: &ChannelCache) -> usize {
+        let user_id = App::user_id();
+        match cache.present() {
+            Some(index) => cache.segments[index]
+                .messages
+                .iter()
+                .filter(|x| {
+                    x.0 > self.last_seen
+                        && x.1.author_user_id != user_id
+                        && !blocked_users::is_blocked(x.1.author_user_id)
+                })
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Sends ack for messages which were really displayed to the user.
+    fn mark_seen(&mut self, ctx: &Context<Self>, cache: &ChannelCache) {
+        if !WebPage::is_focused() {
+            return;
+        }
+        let segment = match cache.current() {
+            Some(segment) => segment,
+            None => return,
+        };
+
+        let end = Self::bottom_index(cache);
+        let seen = match segment.messages.range(..end).rev().find(|x| x.0 > 0) {
+            Some(message) => message.0,
+            None => return,
+        };
+        if seen <= self.last_seen {
+            return;
+        }
+
+        let user_id = App::user_id();
+        let read_count = segment
+            .messages
+            .iter()
+            .filter(|x| x.0 > self.last_seen && x.0 <= seen && x.1.author_user_id != user_id)
+            .count() as i64;
+        let is_everything_read = segment.is_present && seen == segment.last_id();
+        self.last_seen = seen;
+
+        self.send_ack(ctx, seen);
+        navigator::add_pings(
+            ctx.props().channel_id,
+            match is_everything_read {
+                true => i64::MIN,
+                false => -read_count,
+            },
+            seen,
+        );
+    }
+
+    /// Updates heights of the rendered messages.
+    fn measure(&self, cache: &mut ChannelCache) {
+        let ids = match cache.current() {
+            Some(segment) => {
+                let (start, end) = self.visible_range(cache, segment);
+                segment
+                    .messages
+                    .range(start..end)
+                    .map(|x| x.0)
+                    .collect::<Vec<i64>>()
+            }
+            None => return,
+        };
+
+        let document = WebPage::document();
+        for message_id in ids {
+            if let Some(element) =
+                document.get_element_by_id(&format!("channel-message-{}", message_id))
+            {
+                cache.set_height(message_id, element.client_height());
+            }
+        }
+    }
+
+    fn load(&self, ctx: &Context<Self>, request: PageRequest) {
         let callback = ctx.link().callback(Msg::Load);
         let channel_id = ctx.props().channel_id;
 
         Timeout::new(0, move || {
             wasm_bindgen_futures::spawn_local(async move {
-                callback.emit(encryption::get_messages(channel_id, before).await);
+                callback.emit(request.fetch(channel_id).await);
             });
         })
         .forget();
     }
 
-    fn load_set(&mut self, ctx: &Context<Self>, messages: Vec<ChannelMessage>) {
+    fn load_set(&mut self, ctx: &Context<Self>, page: LoadedPage) {
         if self.cache.is_none() {
-            self.cache = Some(
-                CACHED_CHANNELS
-                    .lock()
-                    .unwrap()
+            self.cache = Some(CACHED_CHANNELS.with(|channels| {
+                channels
+                    .borrow_mut()
                     .get_or_insert(ctx.props().channel_id, || {
-                        Arc::new(Mutex::new(ChannelCache {
-                            messages: Vec::new(),
-                            is_scrolled_to_top: false,
-                            scroll_y: 0,
-                        }))
+                        Rc::new(RefCell::new(ChannelCache::new()))
                     })
-                    .clone(),
-            );
+                    .clone()
+            }));
+        }
+        let destination = self.cache.clone().unwrap();
+        let mut lock = destination.borrow_mut();
+
+        let around = match &page {
+            LoadedPage::After(..) => {
+                self.keep_scroll_top = Some(Element::by_id("channel-content-scroll").scroll_top());
+                None
+            }
+            LoadedPage::Around(message_id, ..) => Some(*message_id),
+            _ => None,
+        };
+        lock.apply(page);
+
+        if let Some((message_id, highlight)) = self.pending_jump {
+            match lock.find_segment(message_id) {
+                Some(index) => {
+                    self.pending_jump = None;
+                    lock.current = index;
+                    self.keep_scroll_top = None;
+                    self.jump_to = Some(message_id);
+                    if highlight {
+                        self.highlighted = Some(message_id);
+                    }
+                }
+                // Page around the message was loaded without it, so it was deleted.
+                None if around == Some(message_id) => {
+                    self.pending_jump = None;
+                    self.highlighted = None;
+                    self.status = Status::with_err_key("viewChannelMessageNotFound");
+                }
+                None => (),
+            }
         }
-        let destination = self.cache.as_ref().unwrap();
-        let mut lock = destination.lock().unwrap();
+    }
 
-        if messages.len() < 50 {
-            lock.is_scrolled_to_top = true;
+    fn jump(&mut self, ctx: &Context<Self>, message_id: i64, highlight: bool) {
+        self.status = Html::default();
+        if highlight {
+            self.highlighted = Some(message_id);
         }
 
-        for message in messages {
-            lock.messages
-                .insert(0, (message.message_id, message.clone()));
+        if let Some(cache) = &self.cache {
+            let mut lock = cache.borrow_mut();
+            if let Some(index) = lock.find_segment(message_id) {
+                lock.current = index;
+                self.jump_to = Some(message_id);
+                return;
+            }
         }
+
+        // Message is outside of loaded history, so load the page around it.
+        self.pending_jump = Some((message_id, highlight));
+        self.load(ctx, PageRequest::Around(message_id));
     }
 
-    fn load_up(&mut self, ctx: &Context<Self>) {
+    fn jump_to_present(&mut self, ctx: &Context<Self>) {
         if let Some(cache) = &self.cache {
-            if cache.lock().unwrap().is_scrolled_to_top {
+            let mut lock = cache.borrow_mut();
+            if lock.select_present() {
+                lock.scroll_y = 0;
                 return;
             }
         }
 
+        self.load(ctx, PageRequest::Latest);
+    }
+
+    fn load_up(&mut self, ctx: &Context<Self>) {
         let before = match self.cache.as_ref() {
             Some(arc) => {
-                let cache = arc.lock().unwrap();
-                if cache.messages.is_empty() {
-                    return;
+                let cache = arc.borrow();
+                match cache.current() {
+                    Some(segment) if !segment.is_beginning && !segment.messages.is_empty() => {
+                        segment.first_id()
+                    }
+                    _ => return,
                 }
-                cache.messages[0].1.message_id
             }
             None => return,
         };
 
         if before != self.latest_before {
             self.latest_before = before;
-            self.load(ctx, before);
+            self.load(ctx, PageRequest::Before(before));
+        }
+    }
+
+    fn load_down(&mut self, ctx: &Context<Self>) {
+        let after = match self.cache.as_ref() {
+            Some(arc) => {
+                let cache = arc.borrow();
+                match cache.current() {
+                    Some(segment) if !segment.is_present && !segment.messages.is_empty() => {
+                        segment.last_id()
+                    }
+                    _ => return,
+                }
+            }
+            None => return,
+        };
+
+        if after != self.latest_after {
+            self.latest_after = after;
+            self.load(ctx, PageRequest::After(after));
         }
     }
 
     fn change_channel(&mut self, ctx: &Context<Self>) {
-        OPENED_CHANNEL.set(Arc::new(Some((
-            ctx.props().channel_id,
-            ctx.link().callback(|m| m).into(),
-        ))));
+        let channel_id = ctx.props().channel_id;
+        self.pending_jump = None;
+        self.highlighted = None;
+        self.keep_scroll_top = None;
+        self.status = Html::default();
+        self.cache = cached_channel(channel_id);
+
+        let (unread_count, last_read_message_id) = Self::read_state(channel_id);
+        self.last_seen = last_read_message_id;
+        self.unread_marker = (unread_count > 0).then_some(last_read_message_id);
+
+        if self.take_pending_jump(ctx) {
+            return;
+        }
 
-        let mut lock = CACHED_CHANNELS.lock().unwrap();
-        let messages = lock.get(&ctx.props().channel_id);
-        self.cache = messages.cloned();
+        if self.cache.is_none() {
+            match self.unread_marker {
+                Some(marker) if marker > 0 => self.jump(ctx, marker, false),
+                _ => self.load(ctx, PageRequest::Latest),
+            };
+        }
+    }
 
-        if messages.is_none() {
-            self.load(ctx, 0);
+    /// Jumps to the message requested for this channel, returns whether there was one.
+    fn take_pending_jump(&mut self, ctx: &Context<Self>) -> bool {
+        match *PENDING_JUMP.get() {
+            Some((channel_id, message_id)) if channel_id == ctx.props().channel_id => {
+                PENDING_JUMP.set(Arc::new(None));
+                self.jump(ctx, message_id, true);
+                true
+            }
+            _ => false,
         }
     }
 
-    fn set_scroll(&self, ctx: &Context<Self>, scroll: i32) {
-        if let Some(cache) = &self.cache {
-            let mut lock = cache.lock().unwrap();
-            lock.scroll_y = scroll;
+    fn read_state(channel_id: i64) -> (i64, i64) {
+        store::get::<DirectChannels>()
+            .find(channel_id)
+            .map_or((0, 0), |x| (x.unread_count, x.last_read_direct_message_id))
+    }
 
-            // Send ack.
-            if scroll == 0 && WebPage::is_focused() {
-                let last = lock.messages[lock.messages.len() - 1].1.message_id;
-                self.send_ack(ctx, last);
-                navigator::add_pings(ctx.props().channel_id, i64::MIN, last);
-            }
+    fn set_scroll(&mut self, ctx: &Context<Self>, scroll: i32) {
+        if let Some(cache) = self.cache.clone() {
+            let mut lock = cache.borrow_mut();
+            lock.scroll_y = scroll;
+            self.mark_seen(ctx, &lock);
         }
     }
 
     fn send_ack(&self, ctx: &Context<Self>, last_read_message_id: i64) {
-        api::post("channels/direct/messages/ack")
-            .body(&json!({
-                "directChannelId": ctx.props().channel_id,
-                "lastReadDirectMessageId": last_read_message_id
-            }))
-            .send_without_ok(move |r| match r {
-                ApiResponse::Ok(_) => (),
-                ApiResponse::BadRequest(_) => todo!(),
-            });
+        channels::ack_messages(&MessagesAckRequestData {
+            direct_channel_id: ctx.props().channel_id,
+            last_read_direct_message_id: last_read_message_id,
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(_) => (),
+            ApiResponse::BadRequest(_) => log::error!("Unable to acknowledge messages."),
+        });
     }
 }
 
-fn process_message_view(ctx: LoadUserContext<ChannelMessage>) -> Html {
-    if ctx.user.is_none() {
+#[function_component(MessageView)]
+fn message_view(props: &MessageViewProps) -> Html {
+    let user_id = props.message.author_user_id;
+    // Avatar and name of missing author are replaced by the user views.
+    if use_user(user_id, false) == UserState::Loading {
         return html! { {"Loading..."} };
     }
-    let user = ctx.user.unwrap();
+    let lang = localization::get_language();
+
+    let reply = match props.message.reply_to {
+        Some(parent_id) => {
+            let callback = props.callback.clone();
+            let onclick = Callback::from(move |_| callback.emit(Msg::JumpTo(parent_id)));
+
+            let preview = match &props.parent {
+                Some(parent) => html! { <>
+                    <span class="message-reply-name">{"@"}<UserName user_id={parent.author_user_id} /></span>
+                    <span class="message-reply-text">{
+                        match parent.preview_text() {
+                            Some(text) if !text.is_empty() => text,
+                            Some(_) => lang.get("viewChannelReplyAttachment"),
+                            None => lang.get("encryptionUnableToRead"),
+                        }
+                    }</span>
+                </> },
+                None => html! {
+                    <span class="message-reply-text">{lang.get("viewChannelReplyUnloaded")}</span>
+                },
+            };
+
+            html! {
+                <div class="message-reply noselect" {onclick}>
+                    {preview}
+                </div>
+            }
+        }
+        None => html! {},
+    };
+
+    let message_id = props.message.message_id;
+    let reply_callback = props.reply_callback.clone();
 
-    html! {
+    html! { <>
+        {reply}
         <div class="channel-message channel-message-with-avatar">
-            <img class="message-avatar noselect" src={user.avatar_url.clone()} alt={"avatar"} />
+            <UserAvatar {user_id} class={classes!("message-avatar", "noselect")} with_profile={true} />
             <div>
-                <label class="message-name">{user.name.clone()}</label>
+                <UserName {user_id} class="message-name" with_profile={true} />
                 <br/>
-                <label>{ctx.props.get_html().clone()}</label>
+                <label>{props.message.get_html().clone()}</label>
+            </div>
+            <div class="channel-message-actions">
+                <button onclick={Callback::from(move |_| reply_callback.emit(message_id))}>{
+                    lang.get("viewChannelReply")
+                }</button>
             </div>
         </div>
-    }
+    </> }
 }
@@ -2,7 +2,7 @@ use lazy_static::__Deref;
 use std::sync::Arc;
 use yew::prelude::*;
 
-use crate::{api, common::UnsafeSync, localization};
+use crate::{api, localization};
 
 use super::channel_message_error::ChannelMessageError;
 
@@ -15,8 +15,9 @@ static VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "webm", "ogg", "wav"];
 pub struct ChannelMessage {
     pub message_id: i64,
     pub author_user_id: i64,
+    pub reply_to: Option<i64>,
     content: Result<Arc<String>, ChannelMessageError>,
-    html: UnsafeSync<Html>,
+    html: Html,
 }
 
 impl ChannelMessage {
@@ -43,11 +44,17 @@ impl ChannelMessage {
             }
         };
 
+        let reply_to = match &content {
+            Ok(content) => Self::find_reply(content),
+            Err(_) => None,
+        };
+
         Self {
             message_id,
             author_user_id,
+            reply_to,
             content,
-            html: UnsafeSync(html),
+            html,
         }
     }
 
@@ -55,9 +62,52 @@ impl ChannelMessage {
         &self.html
     }
 
+    pub fn reply_pointer(message_id: i64) -> String {
+        format!("<^r/{}>", message_id)
+    }
+
+    /// Content of a message to send. Reply pointer typed by the user at the start is moved from
+    /// there, so it is not read as a reply.
+    pub fn with_reply(mut content: String, reply_to: Option<i64>) -> String {
+        match reply_to {
+            Some(reply_to) => content.insert_str(0, &Self::reply_pointer(reply_to)),
+            None if content.starts_with("<^r/") => content.insert(0, ' '),
+            None => (),
+        }
+        content
+    }
+
+    pub fn text(&self) -> Option<String> {
+        let mut content = match &self.content {
+            Ok(content) => content.as_str(),
+            Err(_) => return None,
+        };
+
+        let mut result = String::new();
+        while let Some((c, d)) = Self::find_pointer(content) {
+            result.push_str(&content[..c]);
+            content = &content[d + 1..];
+        }
+        result.push_str(content);
+
+        Some(result.trim().to_owned())
+    }
+
+    pub fn preview_text(&self) -> Option<String> {
+        self.text().map(|text| text.chars().take(100).collect())
+    }
+
+    fn find_reply(content: &str) -> Option<i64> {
+        if !content.starts_with("<^r/") {
+            return None;
+        }
+        let end = content.find('>')?;
+        content[4..end].parse::<i64>().ok()
+    }
+
     fn find_pointer(content: &str) -> Option<(usize, usize)> {
-        if let Some(c) = content.chars().position(|c| c == '<') {
-            if let Some(d) = content.chars().position(|c| c == '>') {
+        if let Some(c) = content.find('<') {
+            if let Some(d) = content.find('>') {
                 if d as isize - c as isize > 1 {
                     return Some((c, d));
                 }
@@ -101,7 +151,7 @@ impl ChannelMessage {
                 name
             );
 
-            let extension = name.split('.').last().unwrap_or("").to_lowercase();
+            let extension = name.split('.').next_back().unwrap_or("").to_lowercase();
             if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
                 vec.push(html! { <img class="channel-message-embeded" src={url} /> });
             } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
@@ -128,3 +178,28 @@ impl ChannelMessage {
         html! { <>{vec}</> }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    fn text_skips_pointers_after_non_ascii_characters() {
+        let content = format!("é{}ünïcode", ChannelMessage::reply_pointer(1));
+        let message = ChannelMessage::new(2, 3, Ok(Arc::new(content)));
+        assert_eq!(message.text().as_deref(), Some("éünïcode"));
+    }
+
+    #[wasm_bindgen_test]
+    fn typed_reply_pointer_is_not_reply() {
+        let content = ChannelMessage::with_reply("<^r/1> hello".to_owned(), None);
+        let message = ChannelMessage::new(2, 3, Ok(Arc::new(content)));
+        assert_eq!(message.reply_to, None);
+
+        let content = ChannelMessage::with_reply("<^r/1> hello".to_owned(), Some(4));
+        let message = ChannelMessage::new(2, 3, Ok(Arc::new(content)));
+        assert_eq!(message.reply_to, Some(4));
+    }
+}
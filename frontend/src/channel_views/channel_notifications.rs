@@ -0,0 +1,161 @@
+use wasm_bindgen::JsValue;
+use yew::prelude::*;
+
+use crate::{
+    localization,
+    notifications::{self, ChannelNotificationSettings, NotificationLevel},
+};
+
+const MUTE_DURATIONS: [(i64, &str); 5] = [
+    (15, "viewChannelNotificationsMute15Minutes"),
+    (60, "viewChannelNotificationsMuteHour"),
+    (8 * 60, "viewChannelNotificationsMute8Hours"),
+    (24 * 60, "viewChannelNotificationsMute24Hours"),
+    (0, "viewChannelNotificationsMuteForever"),
+];
+
+pub struct ChannelNotifications {
+    settings: ChannelNotificationSettings,
+    is_open: bool,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub channel_id: i64,
+}
+
+pub enum Msg {
+    Toggle,
+    SetLevel(NotificationLevel),
+    /// Mutes the channel for the given count of minutes, zero mutes it until unmuted.
+    Mute(i64),
+    Unmute,
+}
+
+impl Component for ChannelNotifications {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            settings: notifications::channel_settings(ctx.props().channel_id),
+            is_open: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Toggle => {
+                self.is_open = !self.is_open;
+                return true;
+            }
+            Msg::SetLevel(level) => self.settings.level = level,
+            Msg::Mute(minutes) => {
+                self.settings.muted_until = match minutes {
+                    0 => i64::MAX,
+                    _ => js_sys::Date::now() as i64 + minutes * 60 * 1000,
+                };
+                self.is_open = false;
+            }
+            Msg::Unmute => self.settings.muted_until = 0,
+        };
+
+        notifications::set_channel_settings(ctx.props().channel_id, self.settings);
+        true
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().channel_id != old_props.channel_id {
+            self.settings = notifications::channel_settings(ctx.props().channel_id);
+            self.is_open = false;
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+        let is_muted = self.settings.is_muted();
+
+        let button_text = match is_muted {
+            true => lang.get("viewChannelNotificationsMuted"),
+            false => lang.get("viewChannelNotifications"),
+        };
+        if !self.is_open {
+            return html! {
+                <div class="channel-notifications">
+                    <button onclick={ctx.link().callback(|_| Msg::Toggle)}>{button_text}</button>
+                </div>
+            };
+        }
+
+        let mute = match is_muted {
+            true => html! { <>
+                <p>{muted_until_text(self.settings.muted_until)}</p>
+                <button onclick={ctx.link().callback(|_| Msg::Unmute)}>{
+                    lang.get("viewChannelNotificationsUnmute")
+                }</button>
+            </> },
+            false => MUTE_DURATIONS
+                .iter()
+                .map(|(minutes, translation_key)| {
+                    let minutes = *minutes;
+                    html! {
+                        <button onclick={ctx.link().callback(move |_| Msg::Mute(minutes))}>{
+                            lang.get(translation_key)
+                        }</button>
+                    }
+                })
+                .collect::<Html>(),
+        };
+
+        let levels = [
+            (NotificationLevel::All, "viewChannelNotificationsAll"),
+            (
+                NotificationLevel::Mentions,
+                "viewChannelNotificationsMentions",
+            ),
+            (
+                NotificationLevel::Nothing,
+                "viewChannelNotificationsNothing",
+            ),
+        ]
+        .into_iter()
+        .map(|(level, translation_key)| {
+            let class = match self.settings.level == level {
+                true => "button-selected",
+                false => "",
+            };
+            html! {
+                <button {class} onclick={ctx.link().callback(move |_| Msg::SetLevel(level))}>{
+                    lang.get(translation_key)
+                }</button>
+            }
+        })
+        .collect::<Html>();
+
+        html! {
+            <div class="channel-notifications">
+                <button onclick={ctx.link().callback(|_| Msg::Toggle)}>{button_text}</button>
+                <div class="channel-notifications-menu">
+                    <h4>{lang.get("viewChannelNotificationsMute")}</h4>
+                    {mute}
+                    <h4>{lang.get("viewChannelNotificationsLevel")}</h4>
+                    {levels}
+                </div>
+            </div>
+        }
+    }
+}
+
+pub fn muted_until_text(muted_until: i64) -> String {
+    let lang = localization::get_language();
+    if muted_until == i64::MAX {
+        return lang.get("viewChannelNotificationsMutedForever");
+    }
+
+    let date: String = js_sys::Date::new(&JsValue::from_f64(muted_until as f64))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into();
+    lang.get("viewChannelNotificationsMutedUntil")
+        .replace("{date}", &date)
+}
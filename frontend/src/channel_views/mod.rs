@@ -1,4 +1,6 @@
 pub mod channel;
+pub mod channel_cache;
 pub mod channel_content;
 pub mod channel_message;
 pub mod channel_message_error;
+pub mod channel_notifications;
@@ -1,69 +1,51 @@
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
 
-use arc_cell::ArcCell;
-use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
-lazy_static! {
-    pub(crate) static ref INSTANCE: ArcCell<Option<UnsafeSync<Callback<Msg>>>> = ArcCell::default();
-}
-
 use crate::{
-    account::load_user::{LoadUser, LoadUserContext},
-    app,
-    common::UnsafeSync,
-    navigator::{self, NavigatorCache},
+    account::user_views::UserBadge,
+    api::{
+        client::{accounts::UserIdRequestData, channels::get_direct_channel_id},
+        ApiResponse, ErrorDataElement,
+    },
+    app::App,
+    notifications::{self, SettingsChanged},
+    store::{self, channels},
 };
 
-pub struct DirectChannels {
-    cache: Arc<Mutex<NavigatorCache>>,
+/// Opens direct channel with the user, the channel is created when it does not exist yet.
+pub fn open_with_user<F>(user_id: i64, error: F)
+where
+    F: FnOnce(HashMap<String, ErrorDataElement>) + 'static,
+{
+    get_direct_channel_id(&UserIdRequestData { user_id }).send(move |r| match r {
+        ApiResponse::Ok(r) => App::open_channel(r.channel_id),
+        ApiResponse::BadRequest(err) => error(err),
+    });
 }
 
-#[derive(Properties, PartialEq, Clone)]
-pub struct Props {
-    pub app_callback: Callback<app::Msg>,
+pub struct DirectChannels {
+    _subscription: store::Subscription,
+    _settings_subscription: store::Subscription,
 }
 
 pub enum Msg {
     Refresh,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DirectChannelsLoadResponseData {
-    pub direct_channels: Vec<DirectChannelResponseData>,
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct DirectChannelResponseData {
-    pub unread_count: i64,
-    pub recent_activity: i64,
-    pub is_group: bool,
-    pub user_id: i64,
-    pub group_data: Option<GroupResponseData>,
-    pub direct_channel_id: i64,
-    pub last_read_direct_message_id: i64,
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct GroupResponseData {
-    pub name: String,
-    pub avatar_url: String,
-    user_count: i32,
-}
-
 impl Component for DirectChannels {
     type Message = Msg;
-    type Properties = Props;
+    type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let s = Self {
-            cache: navigator::CACHED_DATA.get(),
-        };
-        INSTANCE.set(Arc::new(Some(UnsafeSync(ctx.link().callback(|m| m)))));
-        s
+        Self {
+            _subscription: store::subscribe::<channels::DirectChannels>(
+                ctx.link().callback(|_| Msg::Refresh),
+            ),
+            _settings_subscription: store::listen(
+                ctx.link().callback(|_: SettingsChanged| Msg::Refresh),
+            ),
+        }
     }
 
     fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
@@ -73,40 +55,35 @@ impl Component for DirectChannels {
         true
     }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        let lock = self.cache.lock().unwrap();
-        let data = match &lock.direct_channels {
-            Some(data) => {
+    fn view(&self, _: &Context<Self>) -> Html {
+        let state = store::get::<channels::DirectChannels>();
+        let data = match &state.channels {
+            Some(channels) => {
                 let mut vec = Vec::new();
 
-                for data in &data.direct_channels {
+                for data in channels {
                     if data.is_group {
                         todo!();
                     }
 
-                    let a = ctx.props().app_callback.clone();
                     let channel_id = data.direct_channel_id;
 
+                    let settings = notifications::channel_settings(channel_id);
                     let class = format!(
                         "user-profile-container channel-{}",
-                        match data.unread_count == 0 {
-                            true => "read",
-                            false => "unread",
+                        match settings.badge_count(data.unread_count, data.mention_count) {
+                            0 if !settings.allows(false) => "muted",
+                            0 => "read",
+                            _ => "unread",
                         }
                     );
 
                     vec.push(html! {
                         <div
-                            onclick={Callback::from(move |_| a.emit(app::Msg::OpennedChannel(channel_id)))}
+                            onclick={Callback::from(move |_| App::open_channel(channel_id))}
                             class={class}
                         >
-                            <LoadUser<()>
-                                props={()}
-                                user_id={data.user_id}
-                                view={Callback::from(process_user_channel_view)}
-                                with_status={true}
-                                refresh={false}
-                            />
+                            <UserBadge user_id={data.user_id} with_status={true} />
                         </div>
                     })
                 }
@@ -116,32 +93,10 @@ impl Component for DirectChannels {
             None => vec![html! { <p>{"Loading..."}</p> }],
         };
 
-        let a = ctx.props().app_callback.clone();
         html! { <>
-            <p onclick={Callback::from(move |_| a.emit(app::Msg::OpennedChannel(0)))}>{"Friends"}</p>
+            <p onclick={Callback::from(|_| App::open_channel(0))}>{"Friends"}</p>
 
             {data}
         </> }
     }
-
-    fn destroy(&mut self, _: &Context<Self>) {
-        INSTANCE.set(Arc::new(None));
-    }
-}
-
-fn process_user_channel_view(ctx: LoadUserContext<()>) -> Html {
-    if ctx.user.is_none() {
-        return html! { {"Loading..."} };
-    }
-    let user = ctx.user.unwrap();
-
-    html! {
-        <div class="user-profile">
-            <img class="user-avatar noselect" src={user.avatar_url.to_owned()} alt={"avatar"} />
-            {user.status.as_ref().unwrap().icon_html()}
-            <div class="user-content">
-                <span class="user-name">{user.name.to_owned()}</span>
-            </div>
-        </div>
-    }
 }
@@ -1,24 +1,32 @@
 use core::panic;
 use std::{
+    cell::RefCell,
     num::NonZeroUsize,
+    rc::Rc,
     sync::{Arc, Mutex},
 };
 
-use arc_cell::ArcCell;
+use argon2::Argon2;
 use base64::{engine::general_purpose, Engine as _};
 use js_sys::Reflect;
 use lru::LruCache;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{CryptoKey, CryptoKeyPair};
 
 use crate::{
-    api::{self, ErrorData, ErrorDataElement, Platform},
+    api::{
+        client::channels::{
+            self, DirectChannelRequestData, EncryptionKeyRequestData, EncryptionKeysPutDataElement,
+            EncryptionKeysPutRequestData, EncryptionPrivateKeyRequestData,
+            EncryptionPublicKeyElementResponseData, MessagesGetRequestData, MessagesPutRequestData,
+            MiddleKeysData, PutEncryptionBlockRequestData,
+        },
+        ApiResponse, ErrorDataElement,
+    },
     channel_views::{channel_message::ChannelMessage, channel_message_error::ChannelMessageError},
-    common::UnsafeSync,
     helpers::prelude::WebPage,
+    search_views::search_index,
 };
 
 use super::encryption_error::EncryptionError;
@@ -27,107 +35,39 @@ const RSA_BITS: usize = 4096;
 const AES_BITS: usize = 256;
 const AES_BLOCK_BITS: usize = 64;
 const PRIVATE_KEY_BLOCKS: usize = 8;
+const KEY_BACKUP_HEADER: &[u8] = b"arlekinkeybackup";
+const LOCAL_DATA_KEY_LABEL: &[u8] = b"arlekinlocaldata";
 
 lazy_static! {
-    static ref ENCRYPTION_BLOCK_DATA: ArcCell<Option<UnsafeSync<PrivateKeyEncryptionData>>> =
-        ArcCell::default();
     static ref USED_ENCRYPTION_KEYS: Mutex<LruCache<i64, i64>> =
         Mutex::new(LruCache::new(NonZeroUsize::new(512).unwrap()));
-    static ref CACHED_ENCRYPTION_BLOCKS_PRIVATE: Mutex<LruCache<i64, UnsafeSync<Arc<CryptoKey>>>> =
-        Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap()));
-    static ref CACHED_ENCRYPTION_KEYS: Mutex<LruCache<(i64, i64), Arc<EncryptionKey>>> =
-        Mutex::new(LruCache::new(NonZeroUsize::new(512).unwrap()));
-}
-
-struct PrivateKeyEncryptionData {
-    keys: [CryptoKey; PRIVATE_KEY_BLOCKS],
-}
-
-struct EncryptionKey {
-    encryption_key_id: i64,
-    #[allow(dead_code)]
-    encryption_block_id: i64,
-    key: UnsafeSync<CryptoKey>,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PutEncryptionBlockResponseData {
-    encryption_block_id: i64,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionPublicKeyElementResponseData {
-    platform: Platform,
-    encryption_block_id: i64,
-    public_key: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionPublicKeyResponseData {
-    public_keys: Vec<EncryptionPublicKeyElementResponseData>,
+thread_local! {
+    static ENCRYPTION_BLOCK_DATA: RefCell<Option<Rc<PrivateKeyEncryptionData>>> =
+        const { RefCell::new(None) };
+    static CACHED_ENCRYPTION_BLOCKS_PRIVATE: RefCell<LruCache<i64, Rc<CryptoKey>>> =
+        RefCell::new(LruCache::new(NonZeroUsize::new(100).unwrap()));
+    static CACHED_ENCRYPTION_KEYS: RefCell<LruCache<(i64, i64), Rc<EncryptionKey>>> =
+        RefCell::new(LruCache::new(NonZeroUsize::new(512).unwrap()));
+    static LOCAL_DATA_KEY: RefCell<Option<Rc<CryptoKey>>> = const { RefCell::new(None) };
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionPrivateKeyResponseData {
-    nonce: String,
-    encrypted_private_key: String,
+struct PrivateKeyEncryptionData {
+    keys: [CryptoKey; PRIVATE_KEY_BLOCKS],
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionKeysPutDataElement {
-    encryption_block_id: i64,
-    encrypted_key: String,
+/// Keys protecting private keys of encryption blocks, which are independent of the password.
+#[derive(Clone)]
+pub struct KeyBackup {
+    keys: Vec<Vec<u8>>,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionKeysPutResponseData {
-    encryption_block_id: i64,
+struct EncryptionKey {
     encryption_key_id: i64,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct EncryptionKeysGetEncryptedKeyResponseData {
+    #[allow(dead_code)]
     encryption_block_id: i64,
-    encryption_key_id: i64,
-    encrypted_key: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct MessagesPutResponseData {
-    direct_message_id: i64,
-    send_new_encryption_key: bool,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct GetMiddleKeysResponseData {
-    keys: String,
-    encrypted_keys: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct MessagesGetElementResultData {
-    direct_message_id: i64,
-    author_user_id: i64,
-    encryption_key_id: i64,
-    nonce: String,
-    encrypted_text: String,
-    edited: bool,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct MessagesGetResultData {
-    messages: Vec<MessagesGetElementResultData>,
+    key: CryptoKey,
 }
 
 pub fn try_load() -> bool {
@@ -161,24 +101,25 @@ pub async fn init(encryption_block_hash: &[u8]) {
 }
 
 pub async fn init_worker(encryption_block_hash: &[u8]) {
-    let response = api::post("channels/direct/encryption/getmiddlekeys")
+    let mut is_new = false;
+    let data = match channels::get_middle_keys()
+        .retry_forever()
         .send_async()
-        .await;
-    let data = match response.status() {
-        200 => response.json::<GetMiddleKeysResponseData>().await.unwrap(),
-        400 => {
-            let errors = response.json::<ErrorData>().await.unwrap().errors;
+        .await
+    {
+        ApiResponse::Ok(data) => data,
+        ApiResponse::BadRequest(errors) => {
             if
             // DirectChannelEncryptionMiddleKeysNotFound
             errors.len() == 1
                 && errors.get("").unwrap_or(&ErrorDataElement::default()).code == 3004
             {
+                is_new = true;
                 put_middle_keys(encryption_block_hash).await
             } else {
                 todo!();
             }
         }
-        _ => unreachable!(),
     };
 
     let keys_buffer = general_purpose::STANDARD.decode(data.keys).unwrap();
@@ -199,9 +140,143 @@ pub async fn init_worker(encryption_block_hash: &[u8]) {
         vec.push(import_aes(&encrypted_key_raw).await);
     }
 
-    ENCRYPTION_BLOCK_DATA.set(Arc::new(Some(UnsafeSync(PrivateKeyEncryptionData {
+    let data = PrivateKeyEncryptionData {
         keys: vec.try_into().unwrap(),
-    }))));
+    };
+    ENCRYPTION_BLOCK_DATA.with(|x| *x.borrow_mut() = Some(Rc::new(data)));
+    LOCAL_DATA_KEY.with(|x| *x.borrow_mut() = None);
+
+    // First login of an account, which was registered without logging in, creates also its
+    // first encryption block. It needs the keys above to encrypt the private key.
+    if is_new {
+        put_new_encryption_block(0).await;
+    }
+
+    search_index::load().await;
+}
+
+fn encryption_block() -> Rc<PrivateKeyEncryptionData> {
+    match ENCRYPTION_BLOCK_DATA.with(|x| x.borrow().clone()) {
+        Some(data) => data,
+        None => panic!("Encryption is not initialized."),
+    }
+}
+
+/// Replaces middle keys after the password was reset. Without the key backup, new keys are
+/// generated and history encrypted by old encryption blocks can not be decrypted anymore.
+pub async fn reset(encryption_block_hash: &[u8], backup: Option<KeyBackup>) {
+    let is_restored = backup.is_some();
+    put_middle_keys_worker(encryption_block_hash, backup).await;
+    init(encryption_block_hash).await;
+
+    if !is_restored {
+        put_new_encryption_block(0).await;
+    }
+}
+
+/// Exports current keys encrypted by the passphrase.
+pub async fn export_key_backup(passphrase: &str) -> Vec<u8> {
+    let encryption = encryption_block();
+
+    let mut data = KEY_BACKUP_HEADER.to_vec();
+    for key in &encryption.keys {
+        data.extend_from_slice(&export_key(key, "raw").await);
+    }
+
+    let mut salt_and_nonce = [0u8; 32];
+    WebPage::crypto()
+        .get_random_values_with_u8_array(&mut salt_and_nonce)
+        .unwrap();
+    let (salt, nonce) = salt_and_nonce.split_at(16);
+
+    encrypt_aes(&key_backup_key(passphrase, salt).await, nonce, &mut data).await;
+
+    let mut result = salt_and_nonce.to_vec();
+    result.append(&mut data);
+    result
+}
+
+/// Decrypts the key backup. Returns `None` when the passphrase is wrong or the file is damaged.
+pub async fn import_key_backup(backup: &[u8], passphrase: &str) -> Option<KeyBackup> {
+    if backup.len() <= 32 + KEY_BACKUP_HEADER.len() {
+        return None;
+    }
+    let (salt, backup) = backup.split_at(16);
+    let (nonce, data) = backup.split_at(16);
+
+    let mut data = data.to_vec();
+    decrypt_aes(&key_backup_key(passphrase, salt).await, nonce, &mut data).await;
+
+    let keys = match data.strip_prefix(KEY_BACKUP_HEADER) {
+        Some(keys) if keys.len() % PRIVATE_KEY_BLOCKS == 0 => keys,
+        _ => return None,
+    };
+    Some(KeyBackup {
+        keys: keys
+            .chunks(keys.len() / PRIVATE_KEY_BLOCKS)
+            .map(|x| x.to_vec())
+            .collect(),
+    })
+}
+
+/// Encrypts data which is stored only on this device. Returns the nonce followed by the data.
+pub async fn encrypt_local_data(mut data: Vec<u8>) -> Vec<u8> {
+    let mut nonce = [0u8; 16];
+    WebPage::crypto()
+        .get_random_values_with_u8_array(&mut nonce)
+        .unwrap();
+
+    let key = local_data_key().await;
+    encrypt_aes(&key, &nonce, &mut data).await;
+
+    let mut result = nonce.to_vec();
+    result.append(&mut data);
+    result
+}
+
+/// Decrypts data encrypted by `encrypt_local_data`. Data encrypted by keys which were replaced
+/// since then is returned damaged, so it has to be validated by the caller.
+pub async fn decrypt_local_data(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 16 {
+        return None;
+    }
+    let (nonce, data) = data.split_at(16);
+
+    let mut data = data.to_vec();
+    let key = local_data_key().await;
+    decrypt_aes(&key, nonce, &mut data).await;
+    Some(data)
+}
+
+/// Key of the data stored on this device, derived from keys of the encryption block.
+async fn local_data_key() -> Rc<CryptoKey> {
+    if let Some(key) = LOCAL_DATA_KEY.with(|x| x.borrow().clone()) {
+        return key;
+    }
+
+    let mut material = LOCAL_DATA_KEY_LABEL.to_vec();
+    for key in &encryption_block().keys {
+        material.extend_from_slice(&export_key(key, "raw").await);
+    }
+
+    let promise = WebPage::crypto()
+        .subtle()
+        .digest_with_str_and_u8_array("SHA-256", &material)
+        .expect("Unable to derive local data key.");
+    let array_buffer: js_sys::ArrayBuffer = JsFuture::from(promise).await.unwrap().into();
+    let raw_key = js_sys::Uint8Array::new(&array_buffer).to_vec();
+
+    let key = Rc::new(import_aes(&raw_key).await);
+    LOCAL_DATA_KEY.with(|x| *x.borrow_mut() = Some(key.clone()));
+    key
+}
+
+async fn key_backup_key(passphrase: &str, salt: &[u8]) -> CryptoKey {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .unwrap();
+    import_aes(&key).await
 }
 
 pub async fn put_new_encryption_block(direct_channel_id: i64) {
@@ -209,29 +284,23 @@ pub async fn put_new_encryption_block(direct_channel_id: i64) {
     let (encrypted_private_key, nonce) = encrypt_rsa_private_key(&private_key).await;
     let public_key = export_key(&public_key, "spki").await;
 
-    let response = api::put("channels/direct/encryption")
-        .body(&json!({
-            "directChannelId": direct_channel_id,
-            "publicKey":  general_purpose::STANDARD.encode(public_key),
-            "nonce": general_purpose::STANDARD.encode(nonce),
-            "encryptedPrivateKey": general_purpose::STANDARD.encode(encrypted_private_key)
-        }))
-        .send_async()
-        .await;
-    match response.status() {
-        200 => {
-            let r = response
-                .json::<PutEncryptionBlockResponseData>()
-                .await
-                .unwrap();
-
-            CACHED_ENCRYPTION_BLOCKS_PRIVATE
-                .lock()
-                .unwrap()
-                .put(r.encryption_block_id, Arc::new(private_key).into());
+    let response = channels::put_encryption_block(&PutEncryptionBlockRequestData {
+        direct_channel_id,
+        public_key: general_purpose::STANDARD.encode(public_key),
+        nonce: general_purpose::STANDARD.encode(nonce),
+        encrypted_private_key: general_purpose::STANDARD.encode(encrypted_private_key),
+    })
+    .retry_forever()
+    .send_async()
+    .await;
+    match response {
+        ApiResponse::Ok(r) => {
+            CACHED_ENCRYPTION_BLOCKS_PRIVATE.with(|x| {
+                x.borrow_mut()
+                    .put(r.encryption_block_id, Rc::new(private_key))
+            });
         }
-        400 => {
-            let errors = response.json::<ErrorData>().await.unwrap().errors;
+        ApiResponse::BadRequest(errors) => {
             if
             // ToFast
             errors.len() == 1
@@ -246,29 +315,23 @@ pub async fn put_new_encryption_block(direct_channel_id: i64) {
 
             todo!();
         }
-        _ => unreachable!(),
     };
 }
 
 pub async fn put_new_encryption_key(direct_channel_id: i64) {
     put_new_encryption_block(direct_channel_id).await;
 
-    let response = api::post("channels/direct/encryption/getpublickeys")
-        .body(&json!({ "directChannelId": direct_channel_id }))
+    let response = channels::get_public_keys(&DirectChannelRequestData { direct_channel_id })
+        .retry_forever()
         .send_async()
         .await;
-    match response.status() {
-        200 => {
-            let r = response
-                .json::<EncryptionPublicKeyResponseData>()
-                .await
-                .unwrap();
+    match response {
+        ApiResponse::Ok(r) => {
             put_new_encryption_key_worker(direct_channel_id, r.public_keys).await;
         }
-        400 => {
+        ApiResponse::BadRequest(_) => {
             todo!();
         }
-        _ => unreachable!(),
     };
 }
 
@@ -307,36 +370,51 @@ pub async fn get_messages(
     direct_channel_id: i64,
     before_direct_message_id: i64,
 ) -> Vec<ChannelMessage> {
-    let response = api::get("channels/direct/messages")
-        .query([
-            ("directChannelId", direct_channel_id.to_string()),
-            (
-                "beforeDirectMessageId",
-                before_direct_message_id.to_string(),
-            ),
-        ])
+    get_messages_worker(&MessagesGetRequestData {
+        direct_channel_id,
+        before_direct_message_id: Some(before_direct_message_id),
+        after_direct_message_id: None,
+    })
+    .await
+}
+
+pub async fn get_messages_after(
+    direct_channel_id: i64,
+    after_direct_message_id: i64,
+) -> Vec<ChannelMessage> {
+    get_messages_worker(&MessagesGetRequestData {
+        direct_channel_id,
+        before_direct_message_id: None,
+        after_direct_message_id: Some(after_direct_message_id),
+    })
+    .await
+}
+
+async fn get_messages_worker(request: &MessagesGetRequestData) -> Vec<ChannelMessage> {
+    let direct_channel_id = request.direct_channel_id;
+    let messages = match channels::get_messages(request)
+        .retry_forever()
         .send_async()
-        .await;
-    let messages = match response.status() {
-        200 => response.json::<MessagesGetResultData>().await.unwrap(),
-        400 => todo!(),
-        _ => unreachable!(),
-    }
-    .messages;
+        .await
+    {
+        ApiResponse::Ok(r) => r.messages,
+        ApiResponse::BadRequest(_) => todo!(),
+    };
 
     let mut result = Vec::with_capacity(messages.len());
     for message in messages {
-        result.push(
-            decrypt_message(
-                direct_channel_id,
-                message.direct_message_id,
-                message.author_user_id,
-                message.encryption_key_id,
-                message.nonce,
-                message.encrypted_text,
-            )
-            .await,
-        );
+        let decrypted = decrypt_message(
+            direct_channel_id,
+            message.direct_message_id,
+            message.author_user_id,
+            message.encryption_key_id,
+            message.nonce,
+            message.encrypted_text,
+        )
+        .await;
+
+        search_index::index_message(direct_channel_id, &decrypted, message.created_at);
+        result.push(decrypted);
     }
 
     result
@@ -354,104 +432,105 @@ pub async fn send_message(direct_channel_id: i64, content: String) -> i64 {
     let mut buffer = content.as_bytes().to_vec();
     encrypt_aes(&key.key, &nonce, &mut buffer).await;
 
-    let response = api::put("channels/direct/messages")
-        .body(&json!({
-            "directChannelId": direct_channel_id,
-            "encryptionKeyId": key.encryption_key_id,
-            "nonce": general_purpose::STANDARD.encode(nonce),
-            "encryptedText": general_purpose::STANDARD.encode(buffer)
-        }))
-        .send_async()
-        .await;
-    match response.status() {
-        200 => {
-            let r = response.json::<MessagesPutResponseData>().await.unwrap();
-
-            r.direct_message_id
-        }
-        400 => {
+    let response = channels::send_message(&MessagesPutRequestData {
+        direct_channel_id,
+        encryption_key_id: key.encryption_key_id,
+        nonce: general_purpose::STANDARD.encode(nonce),
+        encrypted_text: general_purpose::STANDARD.encode(buffer),
+    })
+    .retry_forever()
+    .send_async()
+    .await;
+    match response {
+        ApiResponse::Ok(r) => r.direct_message_id,
+        ApiResponse::BadRequest(_) => {
             todo!();
         }
-        _ => unreachable!(),
     }
 }
 
-async fn put_middle_keys(encryption_block_hash: &[u8]) -> GetMiddleKeysResponseData {
+async fn put_middle_keys(encryption_block_hash: &[u8]) -> MiddleKeysData {
+    put_middle_keys_worker(encryption_block_hash, None).await
+}
+
+async fn put_middle_keys_worker(
+    encryption_block_hash: &[u8],
+    backup: Option<KeyBackup>,
+) -> MiddleKeysData {
     let mut keys = Vec::new();
     let mut encrypted_keys = Vec::new();
 
-    for chunk in encryption_block_hash.chunks(encryption_block_hash.len() / PRIVATE_KEY_BLOCKS) {
+    let chunks = encryption_block_hash.chunks(encryption_block_hash.len() / PRIVATE_KEY_BLOCKS);
+    for (i, chunk) in chunks.enumerate() {
         let key = generate_aes().await;
-        let mut encrypted_key = export_key(&generate_aes().await, "raw").await;
+        let mut encrypted_key = match &backup {
+            Some(backup) => backup.keys[i].clone(),
+            None => export_key(&generate_aes().await, "raw").await,
+        };
         encrypt_aes(&key, chunk, &mut encrypted_key).await;
 
         keys.extend_from_slice(&export_key(&key, "raw").await);
         encrypted_keys.extend_from_slice(&encrypted_key);
     }
 
-    let data = GetMiddleKeysResponseData {
+    let data = MiddleKeysData {
         keys: general_purpose::STANDARD.encode(keys),
         encrypted_keys: general_purpose::STANDARD.encode(encrypted_keys),
     };
 
-    let response = api::put("channels/direct/encryption/putmiddlekeys")
-        .body(&data)
+    match channels::put_middle_keys(&data)
+        .retry_forever()
         .send_async()
-        .await;
-    match response.status() {
-        200 => data,
-        400 => todo!(),
-        _ => unreachable!(),
+        .await
+    {
+        ApiResponse::Ok(_) => data,
+        ApiResponse::BadRequest(_) => todo!(),
     }
 }
 
 async fn get_encryption_key(
     direct_channel_id: i64,
     encryption_key_id: i64,
-) -> Result<Arc<EncryptionKey>, EncryptionError> {
+) -> Result<Rc<EncryptionKey>, EncryptionError> {
     loop {
-        if let Some(key) = CACHED_ENCRYPTION_KEYS
-            .lock()
-            .unwrap()
-            .get(&(direct_channel_id, encryption_key_id))
-        {
-            return Ok(key.clone());
+        let cached = CACHED_ENCRYPTION_KEYS.with(|x| {
+            x.borrow_mut()
+                .get(&(direct_channel_id, encryption_key_id))
+                .cloned()
+        });
+        if let Some(key) = cached {
+            return Ok(key);
         }
 
-        let response = api::post("channels/direct/encryption/keys/getencryptedkey")
-            .body(&json!({
-                "directChannelId": direct_channel_id,
-                "encryptionKeyId": encryption_key_id
-            }))
-            .send_async()
-            .await;
-        match response.status() {
-            200 => {
-                let r = response
-                    .json::<EncryptionKeysGetEncryptedKeyResponseData>()
-                    .await
-                    .unwrap();
-
+        let response = channels::get_encrypted_key(&EncryptionKeyRequestData {
+            direct_channel_id,
+            encryption_key_id,
+        })
+        .retry_forever()
+        .send_async()
+        .await;
+        match response {
+            ApiResponse::Ok(r) => {
                 let private_key = get_private_key(direct_channel_id, r.encryption_block_id).await;
                 let mut buffer = general_purpose::STANDARD.decode(r.encrypted_key).unwrap();
                 buffer = decrypt_rsa(&private_key, &mut buffer).await;
                 let key = import_aes(&buffer).await;
 
-                CACHED_ENCRYPTION_KEYS.lock().unwrap().put(
-                    (direct_channel_id, encryption_key_id),
-                    Arc::new(EncryptionKey {
-                        encryption_key_id: r.encryption_key_id,
-                        encryption_block_id: r.encryption_block_id,
-                        key: key.into(),
-                    }),
-                );
+                let key = EncryptionKey {
+                    encryption_key_id: r.encryption_key_id,
+                    encryption_block_id: r.encryption_block_id,
+                    key,
+                };
+                CACHED_ENCRYPTION_KEYS.with(|x| {
+                    x.borrow_mut()
+                        .put((direct_channel_id, encryption_key_id), Rc::new(key))
+                });
                 USED_ENCRYPTION_KEYS
                     .lock()
                     .unwrap()
                     .put(direct_channel_id, encryption_key_id);
             }
-            400 => {
-                let errors = response.json::<ErrorData>().await.unwrap().errors;
+            ApiResponse::BadRequest(errors) => {
                 if
                 // DirectChannelEncryptionKeyNotFound
                 errors.len() == 1
@@ -472,41 +551,32 @@ async fn get_encryption_key(
 
                 todo!();
             }
-            _ => unreachable!(),
         };
     }
 }
 
-async fn get_private_key(direct_channel_id: i64, encryption_block_id: i64) -> Arc<CryptoKey> {
+async fn get_private_key(direct_channel_id: i64, encryption_block_id: i64) -> Rc<CryptoKey> {
     loop {
-        if let Some(key) = CACHED_ENCRYPTION_BLOCKS_PRIVATE
-            .lock()
-            .unwrap()
-            .get(&encryption_block_id)
-        {
-            return key.0.clone();
+        let cached = CACHED_ENCRYPTION_BLOCKS_PRIVATE
+            .with(|x| x.borrow_mut().get(&encryption_block_id).cloned());
+        if let Some(key) = cached {
+            return key;
         }
 
-        let response = api::post("channels/direct/encryption/getprivatekey")
-            .body(&json!({
-                "directChannelId": direct_channel_id,
-                "encryptionBlockId": encryption_block_id
-            }))
-            .send_async()
-            .await;
-        match response.status() {
-            200 => {
-                let r = response
-                    .json::<EncryptionPrivateKeyResponseData>()
-                    .await
-                    .unwrap();
-
+        let response = channels::get_private_key(&EncryptionPrivateKeyRequestData {
+            direct_channel_id,
+            encryption_block_id,
+        })
+        .retry_forever()
+        .send_async()
+        .await;
+        match response {
+            ApiResponse::Ok(r) => {
                 get_private_key_worker(encryption_block_id, r.nonce, r.encrypted_private_key).await;
             }
-            400 => {
+            ApiResponse::BadRequest(_) => {
                 todo!();
             }
-            _ => unreachable!(),
         };
     }
 }
@@ -516,11 +586,7 @@ async fn get_private_key_worker(
     nonce: String,
     encrypted_private_key: String,
 ) {
-    let encryption_block = ENCRYPTION_BLOCK_DATA.get();
-    let encryption = match encryption_block.as_ref() {
-        Some(e) => e,
-        None => panic!("Encryption is not initialized."),
-    };
+    let encryption = encryption_block();
 
     let raw_nonce = general_purpose::STANDARD.decode(nonce).unwrap();
     let mut buffer = general_purpose::STANDARD
@@ -547,10 +613,10 @@ async fn get_private_key_worker(
     }
 
     let private_key = import_rsa(&buffer[0..length], "pkcs8", "decrypt").await;
-    CACHED_ENCRYPTION_BLOCKS_PRIVATE
-        .lock()
-        .unwrap()
-        .put(encryption_block_id, Arc::new(private_key).into());
+    CACHED_ENCRYPTION_BLOCKS_PRIVATE.with(|x| {
+        x.borrow_mut()
+            .put(encryption_block_id, Rc::new(private_key))
+    });
 }
 
 async fn put_new_encryption_key_worker(
@@ -578,35 +644,30 @@ async fn put_new_encryption_key_worker(
         });
     }
 
-    let response = api::put("channels/direct/encryption/keys")
-        .body(&json!({
-            "directChannelId": direct_channel_id,
-            "keyData": elements
-        }))
-        .send_async()
-        .await;
-    match response.status() {
-        200 => {
-            let r = response
-                .json::<EncryptionKeysPutResponseData>()
-                .await
-                .unwrap();
-
-            CACHED_ENCRYPTION_KEYS.lock().unwrap().put(
-                (direct_channel_id, r.encryption_key_id),
-                Arc::new(EncryptionKey {
-                    encryption_key_id: r.encryption_block_id,
-                    encryption_block_id: r.encryption_key_id,
-                    key: UnsafeSync(key),
-                }),
-            );
+    let response = channels::put_encryption_keys(&EncryptionKeysPutRequestData {
+        direct_channel_id,
+        key_data: elements,
+    })
+    .retry_forever()
+    .send_async()
+    .await;
+    match response {
+        ApiResponse::Ok(r) => {
+            let key = EncryptionKey {
+                encryption_key_id: r.encryption_block_id,
+                encryption_block_id: r.encryption_key_id,
+                key,
+            };
+            CACHED_ENCRYPTION_KEYS.with(|x| {
+                x.borrow_mut()
+                    .put((direct_channel_id, r.encryption_key_id), Rc::new(key))
+            });
             USED_ENCRYPTION_KEYS
                 .lock()
                 .unwrap()
                 .put(direct_channel_id, r.encryption_key_id);
         }
-        400 => {
-            let errors = response.json::<ErrorData>().await.unwrap().errors;
+        ApiResponse::BadRequest(errors) => {
             if
             // ToFast
             errors.len() == 1
@@ -621,7 +682,6 @@ async fn put_new_encryption_key_worker(
 
             todo!();
         }
-        _ => unreachable!(),
     };
 }
 
@@ -785,11 +845,7 @@ async fn encrypt_rsa_private_key(
         part.push(private_key_raw[i]);
     }
 
-    let encryption_block = ENCRYPTION_BLOCK_DATA.get();
-    let encryption = match encryption_block.as_ref() {
-        Some(e) => e,
-        None => panic!("Encryption is not initialized."),
-    };
+    let encryption = encryption_block();
 
     let mut nonce: [u8; 16 * PRIVATE_KEY_BLOCKS] = [0; 16 * PRIVATE_KEY_BLOCKS];
     WebPage::crypto()
@@ -825,3 +881,51 @@ fn encryption_aes_algorithm(nonce: &[u8]) -> js_sys::Object {
 
     algorithm
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use serde_json::Value;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::api::test_support::MockTransport;
+
+    #[wasm_bindgen_test]
+    async fn first_login_after_registration_creates_encryption_block() {
+        let requests = Rc::new(RefCell::new(Vec::new()));
+        let _transport = MockTransport::new({
+            let requests = requests.clone();
+            move |path: String, body: Option<String>| {
+                let response = match path.as_str() {
+                    // DirectChannelEncryptionMiddleKeysNotFound
+                    "channels/direct/encryption/getmiddlekeys" => (
+                        400,
+                        r#"{"errors":{"":{"code":3004,"translationKey":""}}}"#.to_owned(),
+                    ),
+                    "channels/direct/encryption/putmiddlekeys" => (200, String::new()),
+                    "channels/direct/encryption" => (200, r#"{"encryptionBlockId":1}"#.to_owned()),
+                    _ => (404, String::new()),
+                };
+                requests.borrow_mut().push((path, body));
+                async move { response }
+            }
+        });
+
+        init_worker(&[7; 128]).await;
+
+        let requests = requests.borrow();
+        let paths = requests.iter().map(|x| x.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "channels/direct/encryption/getmiddlekeys",
+                "channels/direct/encryption/putmiddlekeys",
+                "channels/direct/encryption",
+            ]
+        );
+        let block = serde_json::from_str::<Value>(requests[2].1.as_ref().unwrap()).unwrap();
+        assert_eq!(block["directChannelId"], 0);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::channel_views::channel_content;
+use crate::{channel_views::channel_content, notifications, search_views::search_index};
 
 use super::encryption;
 
@@ -13,19 +13,22 @@ pub struct ReceivedDirectMessageData {
     encryption_key_id: i64,
     nonce: String,
     encrypted_text: String,
+    #[serde(default)]
+    created_at: i64,
 }
 
 pub async fn received_direct_message(data: ReceivedDirectMessageData) {
-    channel_content::notify_message(
+    let message = encryption::decrypt_message(
         data.direct_channel_id,
-        encryption::decrypt_message(
-            data.direct_channel_id,
-            data.direct_message_id,
-            data.author_user_id,
-            data.encryption_key_id,
-            data.nonce,
-            data.encrypted_text,
-        )
-        .await,
-    );
+        data.direct_message_id,
+        data.author_user_id,
+        data.encryption_key_id,
+        data.nonce,
+        data.encrypted_text,
+    )
+    .await;
+
+    search_index::index_message(data.direct_channel_id, &message, data.created_at);
+    notifications::notify_message(data.direct_channel_id, &message);
+    channel_content::notify_message(data.direct_channel_id, message);
 }
@@ -1,5 +1,9 @@
 use img_parts::{jpeg::Jpeg, png::Png, webp::WebP, ImageEXIF};
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, HtmlAnchorElement, Url};
+
+use super::prelude::WebPage;
 
 pub struct File {}
 
@@ -11,10 +15,33 @@ impl File {
         buffer.to_vec()
     }
 
+    /// Saves the bytes as a file through the download of the browser.
+    pub fn download(file_name: &str, bytes: &[u8]) {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+        let blob = Blob::new_with_u8_array_sequence(&parts).unwrap();
+        let url = Url::create_object_url_with_blob(&blob).unwrap();
+
+        let anchor = WebPage::document()
+            .create_element("a")
+            .unwrap()
+            .dyn_into::<HtmlAnchorElement>()
+            .unwrap();
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+
+        Url::revoke_object_url(&url).unwrap();
+    }
+
     pub async fn to_bytes_without_exif(file: &web_sys::File) -> Vec<u8> {
         // TODO: Remove exif data.
         let bytes = Self::to_bytes(file).await;
-        let extension = file.name().split('.').last().unwrap_or("").to_lowercase();
+        let extension = file
+            .name()
+            .split('.')
+            .next_back()
+            .unwrap_or("")
+            .to_lowercase();
 
         let mut result = Vec::new();
         let r = match extension.as_str() {
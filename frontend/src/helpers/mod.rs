@@ -2,5 +2,6 @@ pub mod element;
 pub mod file;
 pub mod input;
 pub mod prelude;
+pub mod select;
 pub mod status;
 pub mod webpage;
@@ -1,5 +1,6 @@
 pub use super::element::Element;
 pub use super::file::File;
 pub use super::input::Input;
+pub use super::select::Select;
 pub use super::status::Status;
 pub use super::webpage::WebPage;
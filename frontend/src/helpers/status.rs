@@ -29,6 +29,14 @@ impl Status {
            </div>
         }
     }
+    pub fn with_err_key(translation_key: &str) -> Html {
+        let lang = localization::get_language();
+        html! {
+            <div class="status-error">
+                <span>{lang.get(translation_key)}</span>
+            </div>
+        }
+    }
     pub fn default_html() -> Html {
         html! {
             <div class="status-error">
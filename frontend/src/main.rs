@@ -9,13 +9,20 @@ pub mod direct_messages_views;
 pub mod helpers;
 pub mod localization;
 pub mod navigator;
+pub mod notifications;
 pub mod notifier;
+pub mod presence;
 pub mod route;
+pub mod search_views;
 pub mod settings_views;
+pub mod store;
 
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(test)]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
     yew::Renderer::<app::AppInit>::new().render();
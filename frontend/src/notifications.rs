@@ -0,0 +1,267 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use web_sys::{Notification, NotificationOptions, NotificationPermission};
+
+use crate::{
+    account::{blocked_users, load_user},
+    app::App,
+    channel_views::channel_message::ChannelMessage,
+    helpers::prelude::*,
+    localization, presence, store,
+};
+
+const STORAGE_KEY: &str = "notification_settings";
+
+lazy_static! {
+    /// Settings of the user with the id, which are loaded.
+    static ref SETTINGS: Mutex<Option<(i64, NotificationSettings)>> = Mutex::new(None);
+}
+
+/// Event emitted when notification settings were changed, which changes badges of channels.
+#[derive(Clone)]
+pub struct SettingsChanged;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Hides content of messages in notifications.
+    pub privacy_mode: bool,
+    #[serde(default)]
+    pub channels: HashMap<i64, ChannelNotificationSettings>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationLevel {
+    #[default]
+    All,
+    Mentions,
+    Nothing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelNotificationSettings {
+    pub level: NotificationLevel,
+    /// Time in milliseconds until which the channel is muted, `i64::MAX` mutes it until unmuted.
+    pub muted_until: i64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            privacy_mode: false,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+impl ChannelNotificationSettings {
+    pub fn is_muted(&self) -> bool {
+        self.muted_until > js_sys::Date::now() as i64
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.level == NotificationLevel::All && !self.is_muted()
+    }
+
+    /// Whether a new message in the channel should notify the user.
+    pub fn allows(&self, is_mention: bool) -> bool {
+        if self.is_muted() {
+            return false;
+        }
+        match self.level {
+            NotificationLevel::All => true,
+            NotificationLevel::Mentions => is_mention,
+            NotificationLevel::Nothing => false,
+        }
+    }
+
+    /// Count displayed on the badge of the channel.
+    pub fn badge_count(&self, unread_count: i64, mention_count: i64) -> i64 {
+        if self.is_muted() {
+            return 0;
+        }
+        match self.level {
+            NotificationLevel::All => unread_count,
+            NotificationLevel::Mentions => mention_count,
+            NotificationLevel::Nothing => 0,
+        }
+    }
+}
+
+pub fn settings() -> NotificationSettings {
+    with_settings(NotificationSettings::clone)
+}
+
+/// Reads the settings of the current user without copying them, they are loaded on the first
+/// use and when another user logs in.
+fn with_settings<T>(f: impl FnOnce(&NotificationSettings) -> T) -> T {
+    let user_id = App::user_id();
+    let mut lock = SETTINGS.lock().unwrap();
+    if !matches!(&*lock, Some((x, _)) if *x == user_id) {
+        let storage = WebPage::local_storage();
+        // Settings were shared by all users of the browser before.
+        storage.remove_item(STORAGE_KEY).unwrap();
+        let settings = storage
+            .get_item(&storage_key(user_id))
+            .unwrap()
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default();
+        *lock = Some((user_id, settings));
+    }
+    f(&lock.as_ref().unwrap().1)
+}
+
+pub fn set_settings(settings: &NotificationSettings) {
+    let user_id = App::user_id();
+    WebPage::local_storage()
+        .set_item(
+            &storage_key(user_id),
+            &serde_json::to_string(settings).unwrap(),
+        )
+        .expect("Unable to set notification_settings to local storage.");
+    *SETTINGS.lock().unwrap() = Some((user_id, settings.clone()));
+    store::emit(SettingsChanged);
+}
+
+fn storage_key(user_id: i64) -> String {
+    format!("{STORAGE_KEY}_{user_id}")
+}
+
+pub fn channel_settings(direct_channel_id: i64) -> ChannelNotificationSettings {
+    with_settings(|settings| {
+        settings
+            .channels
+            .get(&direct_channel_id)
+            .copied()
+            .unwrap_or_default()
+    })
+}
+
+pub fn set_channel_settings(direct_channel_id: i64, channel: ChannelNotificationSettings) {
+    let mut settings = settings();
+    match channel.is_default() {
+        true => settings.channels.remove(&direct_channel_id),
+        false => settings.channels.insert(direct_channel_id, channel),
+    };
+    set_settings(&settings);
+}
+
+/// Message mentions the current user by the username.
+pub fn is_mention(message: &ChannelMessage) -> bool {
+    let user = match load_user::cached_user(App::user_id()) {
+        Some(user) => user,
+        None => return false,
+    };
+    match message.text() {
+        Some(text) => mentions(&text, &user.username),
+        None => false,
+    }
+}
+
+/// Whether the text contains `@username` which is not a part of a longer username.
+fn mentions(text: &str, username: &str) -> bool {
+    let text = text.to_lowercase();
+    let mention = format!("@{}", username.to_lowercase());
+    text.match_indices(&mention).any(|(i, _)| {
+        !text[i + mention.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Notification API is missing in some web views and old browsers.
+pub fn is_supported() -> bool {
+    js_sys::Reflect::has(&WebPage::window(), &JsValue::from_str("Notification")).unwrap_or(false)
+}
+
+pub fn permission() -> NotificationPermission {
+    match is_supported() {
+        true => Notification::permission(),
+        false => NotificationPermission::Denied,
+    }
+}
+
+pub async fn request_permission() -> NotificationPermission {
+    if !is_supported() {
+        return NotificationPermission::Denied;
+    }
+
+    match Notification::request_permission() {
+        Ok(promise) => {
+            let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+        }
+        Err(_) => log::error!("Unable to request notification permission."),
+    }
+    Notification::permission()
+}
+
+/// Displays system notification for message received while the window is not focused.
+/// On desktop the Tauri notification plugin provides the Notification API to the web view.
+pub fn notify_message(direct_channel_id: i64, message: &ChannelMessage) {
+    if WebPage::is_focused()
+        || message.author_user_id == App::user_id()
+        || blocked_users::is_blocked(message.author_user_id)
+    {
+        return;
+    }
+
+    let (enabled, privacy_mode) = with_settings(|x| (x.enabled, x.privacy_mode));
+    if !enabled
+        || !channel_settings(direct_channel_id).allows(is_mention(message))
+        || is_do_not_disturb()
+        || permission() != NotificationPermission::Granted
+    {
+        return;
+    }
+
+    let lang = localization::get_language();
+    let title = match load_user::cached_user(message.author_user_id) {
+        Some(user) => user.name.clone(),
+        None => lang.get("notificationsNewMessage"),
+    };
+    let body = match privacy_mode {
+        true => lang.get("notificationsHiddenContent"),
+        false => match message.text() {
+            Some(text) if !text.is_empty() => text,
+            _ => lang.get("notificationsAttachment"),
+        },
+    };
+
+    let options = NotificationOptions::new();
+    options.set_body(&body);
+    options.set_tag(&format!("direct-channel-{direct_channel_id}"));
+
+    match Notification::new_with_options(&title, &options) {
+        Ok(notification) => {
+            let onclick = Closure::once_into_js(move || {
+                let _ = WebPage::window().focus();
+                App::open_channel(direct_channel_id);
+            });
+            notification.set_onclick(Some(onclick.unchecked_ref()));
+        }
+        Err(_) => log::error!("Unable to display notification."),
+    }
+}
+
+fn is_do_not_disturb() -> bool {
+    presence::presence().status == presence::STATUS_DO_NOT_DISTURB
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    fn mention_ends_at_word_boundary() {
+        assert!(mentions("hi @Bob!", "bob"));
+        assert!(mentions("@bobby and @bob", "bob"));
+        assert!(!mentions("hi @bobby", "bob"));
+        assert!(!mentions("hi @bob_2", "bob"));
+    }
+}
@@ -1,45 +1,35 @@
-use std::{cell::RefCell, sync::Arc};
+use std::{cell::RefCell, rc::Rc};
 
-use arc_cell::ArcCell;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::from_value;
-use uuid::Uuid;
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
 use wasm_sockets::WebSocketError;
 
 use crate::{
-    account,
-    api::{self, ApiResponse},
-    common::UnsafeSync,
+    account::{self, friends_views},
+    api::{
+        client::accounts::{self, GetWsResponseData},
+        ApiResponse, RequestError,
+    },
+    app::App,
     direct_messages_views,
     helpers::prelude::*,
 };
 
-lazy_static! {
-    static ref WEB_SOCKET: ArcCell<Option<WebSocket>> = ArcCell::default();
-}
-
-struct WebSocket {
-    client: UnsafeSync<Arc<RefCell<wasm_sockets::PollingClient>>>,
-}
+type Client = Rc<RefCell<wasm_sockets::PollingClient>>;
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct GetWsResponseData {
-    token: Uuid,
-    address: String,
+thread_local! {
+    /// Client which is connected, `None` while the connection is being made.
+    static WEB_SOCKET: RefCell<Option<Client>> = const { RefCell::new(None) };
 }
 
 pub fn connect() {
-    let cb: Closure<dyn FnMut()> = Closure::new(move || {
-        if let Some(ws) = WEB_SOCKET.get().as_ref() {
-            let borrowed = ws.client.borrow();
-            borrowed.send_string(";").unwrap();
-        } else {
-            reconnect();
-        }
-    });
+    let cb: Closure<dyn FnMut()> =
+        Closure::new(move || match WEB_SOCKET.with(|x| x.borrow().clone()) {
+            Some(client) => client.borrow().send_string(";").unwrap(),
+            None => reconnect(),
+        });
     WebPage::window()
         .set_interval_with_callback_and_timeout_and_arguments_0(cb.as_ref().unchecked_ref(), 60000)
         .unwrap();
@@ -49,17 +39,18 @@ pub fn connect() {
 }
 
 fn reconnect() {
-    WEB_SOCKET.set(Arc::new(None));
-    api::get("accounts/getws").send(move |r: ApiResponse<GetWsResponseData>| match r {
+    WEB_SOCKET.with(|x| *x.borrow_mut() = None);
+    accounts::get_ws().send(move |r| match r {
         ApiResponse::Ok(r) => {
             connect_worker(r).unwrap();
         }
-        ApiResponse::BadRequest(_) => todo!(),
+        // Interval of the connection tries it again.
+        ApiResponse::BadRequest(_) => log::error!("Unable to connect to the notifier."),
     });
 }
 
 fn connect_worker(data: GetWsResponseData) -> Result<(), WebSocketError> {
-    let client = Arc::new(RefCell::new(wasm_sockets::PollingClient::new(&format!(
+    let client = Rc::new(RefCell::new(wasm_sockets::PollingClient::new(&format!(
         "{}/api/v1/notifier/ws?token={}",
         data.address, data.token
     ))?));
@@ -71,9 +62,7 @@ fn connect_worker(data: GetWsResponseData) -> Result<(), WebSocketError> {
         .set_on_connection(Some(Box::new(
             move |_client: &wasm_sockets::EventClient| {
                 log::info!("Connection successfully created");
-                WEB_SOCKET.set(Arc::new(Some(WebSocket {
-                    client: clone.clone().into(),
-                })));
+                WEB_SOCKET.with(|x| *x.borrow_mut() = Some(clone.clone()));
             },
         )));
 
@@ -122,6 +111,41 @@ async fn process_message(message: wasm_sockets::Message) {
         }
         // ReceivedUserStatus
         1 => account::load_user::received_user_status(from_value(data).unwrap()),
+        // ReceivedFriendRequest
+        2 => friends_views::notifier_process::received_friend_request(from_value(data).unwrap()),
+        // AcceptedFriendRequest
+        3 => friends_views::notifier_process::accepted_friend_request(from_value(data).unwrap()),
+        // RejectedFriendRequest
+        4 => friends_views::notifier_process::rejected_friend_request(from_value(data).unwrap()),
+        // CancelledFriendRequest
+        5 => friends_views::notifier_process::cancelled_friend_request(from_value(data).unwrap()),
+        // RevokedSession
+        6 => revoked_session(from_value(data).unwrap()).await,
         _ => unimplemented!(),
     };
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokedSessionData {
+    session_id: i64,
+}
+
+/// Logs out when the revoked session is the session of this client. Requests of a revoked
+/// session are unauthorized, or the session is not listed anymore.
+async fn revoked_session(data: RevokedSessionData) {
+    let response = match accounts::get_sessions().try_send_async().await {
+        Ok(ApiResponse::Ok(response)) => response,
+        // Request of the revoked session is not authorized anymore.
+        Err(RequestError::Unauthorized) => return App::logout_without_api(),
+        Ok(ApiResponse::BadRequest(_)) | Err(_) => {
+            log::error!("Unable to check revoked session.");
+            return;
+        }
+    };
+
+    let current = response.sessions.iter().find(|x| x.is_current);
+    if current.is_none_or(|x| x.session_id == data.session_id) {
+        App::logout_without_api();
+    }
+}
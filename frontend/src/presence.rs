@@ -0,0 +1,164 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicI64, Ordering},
+    Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::Closure, JsCast};
+
+use crate::{
+    account::load_user::{self, UserStatus},
+    api::{
+        client::accounts::{self, StatusRequestData},
+        ApiResponse,
+    },
+    app::App,
+    helpers::prelude::*,
+};
+
+const STORAGE_KEY: &str = "presence";
+const IDLE_TIMEOUT: i64 = 5 * 60 * 1000;
+const CHECK_INTERVAL: i32 = 15000;
+
+pub const STATUS_OFFLINE: i32 = 0;
+pub const STATUS_ONLINE: i32 = 1;
+pub const STATUS_IDLE: i32 = 2;
+pub const STATUS_DO_NOT_DISTURB: i32 = 3;
+
+lazy_static! {
+    static ref PRESENCE: Mutex<Option<Presence>> = Mutex::new(None);
+    static ref SENT: Mutex<Option<(i32, String)>> = Mutex::new(None);
+    static ref LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+    static ref STARTED: AtomicBool = AtomicBool::new(false);
+    static ref ACTIVE: AtomicBool = AtomicBool::new(false);
+}
+
+/// Status chosen by the user. Offline status makes the user invisible to others.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Presence {
+    pub status: i32,
+    pub custom_text: String,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self {
+            status: STATUS_ONLINE,
+            custom_text: String::new(),
+        }
+    }
+}
+
+pub fn presence() -> Presence {
+    let mut lock = PRESENCE.lock().unwrap();
+    lock.get_or_insert_with(|| {
+        WebPage::local_storage()
+            .get_item(STORAGE_KEY)
+            .unwrap()
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default()
+    })
+    .clone()
+}
+
+pub fn set_presence(presence: Presence) {
+    WebPage::local_storage()
+        .set_item(STORAGE_KEY, &serde_json::to_string(&presence).unwrap())
+        .expect("Unable to set presence to local storage.");
+    *PRESENCE.lock().unwrap() = Some(presence);
+    update();
+}
+
+/// Starts broadcasting of the status of the logged in user.
+pub fn start() {
+    LAST_ACTIVITY.store(js_sys::Date::now() as i64, Ordering::Relaxed);
+    *SENT.lock().unwrap() = None;
+    ACTIVE.store(true, Ordering::Relaxed);
+
+    if !STARTED.swap(true, Ordering::Relaxed) {
+        let activity: Closure<dyn FnMut()> = Closure::new(activity);
+        let window = WebPage::window();
+        for event in ["focus", "mousemove", "keydown", "touchstart"] {
+            window
+                .add_event_listener_with_callback(event, activity.as_ref().unchecked_ref())
+                .unwrap();
+        }
+        activity.forget();
+
+        let check: Closure<dyn FnMut()> = Closure::new(update);
+        window
+            .set_interval_with_callback_and_timeout_and_arguments_0(
+                check.as_ref().unchecked_ref(),
+                CHECK_INTERVAL,
+            )
+            .unwrap();
+        check.forget();
+    }
+
+    update();
+}
+
+/// Stops broadcasting and forgets the status, so it is not used by the next logged in user.
+pub fn stop() {
+    ACTIVE.store(false, Ordering::Relaxed);
+    *PRESENCE.lock().unwrap() = None;
+    WebPage::local_storage().remove_item(STORAGE_KEY).unwrap();
+}
+
+fn activity() {
+    let was_inactive = is_inactive();
+    LAST_ACTIVITY.store(js_sys::Date::now() as i64, Ordering::Relaxed);
+    if was_inactive {
+        update();
+    }
+}
+
+/// User is inactive when the window was not focused or used for some time.
+fn is_inactive() -> bool {
+    js_sys::Date::now() as i64 - LAST_ACTIVITY.load(Ordering::Relaxed) > IDLE_TIMEOUT
+}
+
+fn update() {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let presence = presence();
+    let status = match presence.status {
+        STATUS_ONLINE if is_inactive() => STATUS_IDLE,
+        status => status,
+    };
+
+    let value = (status, presence.custom_text.clone());
+    if SENT.lock().unwrap().as_ref() == Some(&value) {
+        return;
+    }
+
+    // Rich presence is set by other clients, so the current one is kept.
+    let rich_presence = load_user::cached_user(App::user_id())
+        .and_then(|x| x.status.as_ref().map(|x| x.rich_presence.clone()))
+        .unwrap_or_default();
+
+    accounts::put_status(&StatusRequestData {
+        status,
+        rich_presence: rich_presence.clone(),
+        custom_status: presence.custom_text.clone(),
+    })
+    // Status is sent again by the next check.
+    .retries(2)
+    .send(move |r| match r {
+        ApiResponse::Ok(_) => *SENT.lock().unwrap() = Some(value),
+        ApiResponse::BadRequest(_) => log::error!("Failed to set status."),
+    });
+
+    load_user::reload_user_status(
+        App::user_id(),
+        UserStatus {
+            status,
+            mobile: false,
+            rich_presence,
+            custom_status: presence.custom_text,
+        },
+    );
+}
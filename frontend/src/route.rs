@@ -1,35 +1,205 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
 use yew::prelude::*;
-use yew_router::prelude::*;
+use yew_router::{
+    history::{BrowserHistory, History},
+    prelude::*,
+};
 
 use crate::app::App;
 
-#[derive(Clone, Routable, PartialEq)]
+#[derive(Clone, Routable, PartialEq, Debug)]
 pub enum Route {
     #[at("/")]
     Home,
     #[at("/login")]
     Login,
+    #[at("/register")]
+    Register,
+    #[at("/reset-password")]
+    PasswordReset,
     #[at("/friends")]
     Friends,
+    #[at("/friends/:tab")]
+    FriendsTab { tab: FriendsTab },
     #[at("/settings")]
     Settings,
+    #[at("/settings/:page")]
+    SettingsPage { page: SettingsPage },
     #[at("/direct/:id")]
     Direct { id: i64 },
+    #[not_found]
+    #[at("/404")]
+    NotFound,
+}
+
+/// Implements conversion of the enum from and to the segment of the path.
+macro_rules! path_segments {
+    ($name:ident { $($variant:ident => $segment:literal,)* }) => {
+        impl FromStr for $name {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($segment => Ok(Self::$variant),)*
+                    _ => Err(()),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => $segment,)*
+                })
+            }
+        }
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FriendsTab {
+    Online,
+    All,
+    Pending,
+    Blocked,
+    Add,
+}
+
+path_segments!(FriendsTab {
+    Online => "online",
+    All => "all",
+    Pending => "pending",
+    Blocked => "blocked",
+    Add => "add",
+});
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsPage {
+    MyAccount,
+    Security,
+    Sessions,
+    Notifications,
+}
+
+path_segments!(SettingsPage {
+    MyAccount => "account",
+    Security => "security",
+    Sessions => "sessions",
+    Notifications => "notifications",
+});
+
+/// Query of the login route, with the route which was opened before the user logged in.
+#[derive(Serialize, Deserialize)]
+struct LoginQuery {
+    redirect: String,
+}
+
+impl Route {
+    pub fn friends(tab: FriendsTab) -> Self {
+        match tab {
+            FriendsTab::Online => Route::Friends,
+            tab => Route::FriendsTab { tab },
+        }
+    }
+
+    pub fn settings(page: SettingsPage) -> Self {
+        match page {
+            SettingsPage::MyAccount => Route::Settings,
+            page => Route::SettingsPage { page },
+        }
+    }
+
+    /// Direct channel, or friends when the id is zero.
+    pub fn channel(channel_id: i64) -> Self {
+        match channel_id {
+            0 => Route::Friends,
+            id => Route::Direct { id },
+        }
+    }
+
+    pub fn requires_login(&self) -> bool {
+        !matches!(
+            self,
+            Route::Login | Route::Register | Route::PasswordReset | Route::NotFound
+        )
+    }
+
+    pub fn friends_tab(&self) -> Option<FriendsTab> {
+        match self {
+            Route::Home | Route::Friends => Some(FriendsTab::Online),
+            Route::FriendsTab { tab } => Some(*tab),
+            _ => None,
+        }
+    }
+
+    pub fn settings_page(&self) -> Option<SettingsPage> {
+        match self {
+            Route::Settings => Some(SettingsPage::MyAccount),
+            Route::SettingsPage { page } => Some(*page),
+            _ => None,
+        }
+    }
+}
+
+pub fn switch(route: Route) -> Html {
+    html! { <App {route} /> }
+}
+
+/// Opens the route as a new entry of the browser history.
+pub fn navigate(route: Route) {
+    BrowserHistory::new().push(route.to_path());
 }
 
-#[derive(Properties, PartialEq, Clone)]
-pub struct Props {
-    pub route: Route,
+/// Replaces the current entry of the browser history by the route.
+pub fn redirect(route: Route) {
+    BrowserHistory::new().replace(route.to_path());
 }
 
-pub fn switch(_: Route) -> Html {
-    html! { <App /> }
+/// Opens login, which returns to the route after the user logs in.
+pub fn redirect_to_login(from: &Route) {
+    let history = BrowserHistory::new();
+    match from {
+        Route::Home => history.replace(Route::Login.to_path()),
+        from => history
+            .replace_with_query(
+                Route::Login.to_path(),
+                LoginQuery {
+                    redirect: from.to_path(),
+                },
+            )
+            .expect("Unable to encode the login query."),
+    }
 }
 
-#[function_component(Router)]
-pub fn router(props: &Props) -> Html {
-    let navigator = use_navigator().unwrap();
-    navigator.push(&props.route);
+/// Route which was opened before the user was redirected to login.
+pub fn login_redirect() -> Option<Route> {
+    let query = BrowserHistory::new()
+        .location()
+        .query::<LoginQuery>()
+        .ok()?;
+    Route::recognize(&query.redirect).filter(Route::requires_login)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    fn routes_round_trip_through_paths() {
+        let routes = [
+            Route::friends(FriendsTab::Pending),
+            Route::settings(SettingsPage::Notifications),
+            Route::channel(12),
+        ];
+        for route in routes {
+            assert_eq!(Route::recognize(&route.to_path()), Some(route));
+        }
 
-    html! {}
+        assert_eq!(Route::friends(FriendsTab::Online).to_path(), "/friends");
+        assert_eq!(Route::recognize("/friends/unknown"), Some(Route::NotFound));
+    }
 }
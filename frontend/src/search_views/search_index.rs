@@ -0,0 +1,344 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use gloo_timers::callback::Timeout;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::App, channel_views::channel_message::ChannelMessage, direct_messages_views::encryption,
+    helpers::prelude::*,
+};
+
+const STORAGE_KEY: &str = "search_index";
+const MAX_DOCUMENTS: usize = 20000;
+/// Only the newest documents are saved, to stay within the quota of the local storage.
+const MAX_STORED_DOCUMENTS: usize = 5000;
+const MAX_RESULTS: usize = 50;
+const SAVE_INTERVAL_MILLIS: u32 = 30000;
+
+lazy_static! {
+    static ref INDEX: Mutex<SearchIndex> = Mutex::new(SearchIndex::default());
+    static ref SAVE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+}
+
+#[derive(Default)]
+struct SearchIndex {
+    documents: HashMap<i64, IndexedMessage>,
+    tokens: HashMap<String, HashSet<i64>>,
+    /// User whose saved index was loaded. Until then, the index is kept only in memory.
+    user_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedMessage {
+    pub direct_message_id: i64,
+    pub direct_channel_id: i64,
+    pub author_user_id: i64,
+    /// `None` when the server did not send the time of the message.
+    pub created_at: Option<i64>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StoredSearchIndex {
+    documents: Vec<IndexedMessage>,
+}
+
+#[derive(Default, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub direct_channel_id: Option<i64>,
+    pub author_user_id: Option<i64>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+/// Loads the index saved by the current user, encrypted by a key derived from the encryption
+/// block. Must be called after the encryption is initialized.
+pub async fn load() {
+    let user_id = App::user_id();
+    if user_id == 0 {
+        return;
+    }
+
+    let storage = WebPage::local_storage();
+    // Index was saved unencrypted and shared by all users before.
+    storage.remove_item(STORAGE_KEY).unwrap();
+    let value = storage
+        .get_item(&storage_key(user_id))
+        .expect("Unable to get search_index from local storage.");
+
+    let stored = match value {
+        Some(value) => {
+            let stored = decrypt_stored(&value).await;
+            if stored.is_none() {
+                log::error!("Search index is corrupted, it will be rebuilt.");
+            }
+            stored
+        }
+        None => None,
+    };
+
+    // The user might log out while the index was decrypted.
+    if App::user_id() != user_id {
+        return;
+    }
+
+    let mut index = INDEX.lock().unwrap();
+    index.user_id = Some(user_id);
+    for document in stored.map(|x| x.documents).unwrap_or_default() {
+        // Messages indexed in the meantime are newer.
+        if !index.documents.contains_key(&document.direct_message_id) {
+            index.insert(document);
+        }
+    }
+
+    let has_unsaved = !index.documents.is_empty();
+    drop(index);
+    if has_unsaved {
+        schedule_save();
+    }
+}
+
+pub fn clear() {
+    *INDEX.lock().unwrap() = SearchIndex::default();
+    WebPage::local_storage().remove_item(STORAGE_KEY).unwrap();
+}
+
+/// Indexes the message. `created_at` is zero when its time is not known.
+pub fn index_message(direct_channel_id: i64, message: &ChannelMessage, created_at: i64) {
+    // Messages which are still being sent have negative temporary ids.
+    if message.message_id <= 0 {
+        return;
+    }
+    let text = match message.text() {
+        Some(text) if !text.is_empty() => text,
+        _ => return,
+    };
+
+    let created_at = match created_at {
+        0 => None,
+        _ => Some(created_at),
+    };
+
+    INDEX.lock().unwrap().insert(IndexedMessage {
+        direct_message_id: message.message_id,
+        direct_channel_id,
+        author_user_id: message.author_user_id,
+        created_at,
+        text,
+    });
+    schedule_save();
+}
+
+pub fn search(query: &SearchQuery) -> Vec<IndexedMessage> {
+    let index = INDEX.lock().unwrap();
+
+    let query_tokens = tokenize(&query.text);
+    let mut found: Option<HashSet<i64>> = None;
+    for query_token in query_tokens {
+        let mut matches = HashSet::new();
+        for (token, ids) in index.tokens.iter() {
+            if token.starts_with(&query_token) {
+                matches.extend(ids);
+            }
+        }
+
+        found = Some(match found {
+            Some(found) => found.intersection(&matches).copied().collect(),
+            None => matches,
+        });
+    }
+
+    let mut result = match found {
+        Some(found) => found
+            .iter()
+            .filter_map(|id| index.documents.get(id))
+            .filter(|x| query.matches(x))
+            .cloned()
+            .collect::<Vec<IndexedMessage>>(),
+        // Without any text only the filters are applied.
+        None => index
+            .documents
+            .values()
+            .filter(|x| query.matches(x))
+            .cloned()
+            .collect::<Vec<IndexedMessage>>(),
+    };
+
+    result.sort_by_key(|x| Reverse(x.direct_message_id));
+    result.truncate(MAX_RESULTS);
+    result
+}
+
+pub fn authors(direct_channel_id: Option<i64>) -> Vec<i64> {
+    let index = INDEX.lock().unwrap();
+    let mut authors = index
+        .documents
+        .values()
+        .filter(|x| direct_channel_id.is_none() || Some(x.direct_channel_id) == direct_channel_id)
+        .map(|x| x.author_user_id)
+        .collect::<Vec<i64>>();
+
+    authors.sort();
+    authors.dedup();
+    authors
+}
+
+fn storage_key(user_id: i64) -> String {
+    format!("{STORAGE_KEY}_{user_id}")
+}
+
+/// Saves the index at most once per the interval, messages are indexed in bursts.
+fn schedule_save() {
+    if INDEX.lock().unwrap().user_id.is_none() || SAVE_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    Timeout::new(SAVE_INTERVAL_MILLIS, || {
+        SAVE_SCHEDULED.store(false, Ordering::Relaxed);
+        wasm_bindgen_futures::spawn_local(save());
+    })
+    .forget();
+}
+
+async fn save() {
+    let (user_id, stored) = {
+        let index = INDEX.lock().unwrap();
+        let user_id = match index.user_id {
+            Some(user_id) => user_id,
+            None => return,
+        };
+
+        let mut documents = index.documents.values().cloned().collect::<Vec<_>>();
+        documents.sort_by_key(|x| Reverse(x.direct_message_id));
+        documents.truncate(MAX_STORED_DOCUMENTS);
+        (user_id, StoredSearchIndex { documents })
+    };
+
+    let data = encryption::encrypt_local_data(serde_json::to_vec(&stored).unwrap()).await;
+
+    // The user might log out while the index was encrypted.
+    if INDEX.lock().unwrap().user_id != Some(user_id) {
+        return;
+    }
+
+    let storage = WebPage::local_storage();
+    let key = storage_key(user_id);
+    if let Err(err) = storage.set_item(&key, &general_purpose::STANDARD.encode(data)) {
+        // Outdated index is removed, messages are indexed again when they are loaded.
+        storage.remove_item(&key).unwrap();
+        log::error!("Unable to save search index to local storage: {:?}", err);
+    }
+}
+
+async fn decrypt_stored(value: &str) -> Option<StoredSearchIndex> {
+    let data = general_purpose::STANDARD.decode(value).ok()?;
+    let data = encryption::decrypt_local_data(&data).await?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_lowercase())
+        .collect()
+}
+
+impl SearchIndex {
+    fn insert(&mut self, document: IndexedMessage) {
+        let id = document.direct_message_id;
+        if self.documents.contains_key(&id) {
+            self.remove(id);
+        }
+
+        for token in tokenize(&document.text) {
+            self.tokens.entry(token).or_default().insert(id);
+        }
+        self.documents.insert(id, document);
+
+        if self.documents.len() > MAX_DOCUMENTS {
+            if let Some(oldest) = self.documents.keys().min().copied() {
+                self.remove(oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: i64) {
+        if let Some(document) = self.documents.remove(&id) {
+            for token in tokenize(&document.text) {
+                if let Some(ids) = self.tokens.get_mut(&token) {
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        self.tokens.remove(&token);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl SearchQuery {
+    fn matches(&self, document: &IndexedMessage) -> bool {
+        if let Some(direct_channel_id) = self.direct_channel_id {
+            if document.direct_channel_id != direct_channel_id {
+                return false;
+            }
+        }
+        if let Some(author_user_id) = self.author_user_id {
+            if document.author_user_id != author_user_id {
+                return false;
+            }
+        }
+        // Documents without the time are not filtered by the date.
+        if let Some(created_at) = document.created_at {
+            if matches!(self.from, Some(from) if created_at < from) {
+                return false;
+            }
+            if matches!(self.to, Some(to) if created_at > to) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    fn document(created_at: Option<i64>) -> IndexedMessage {
+        IndexedMessage {
+            direct_message_id: 1,
+            direct_channel_id: 2,
+            author_user_id: 3,
+            created_at,
+            text: "harlequin".to_owned(),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn date_filter_skips_documents_without_time() {
+        let query = SearchQuery {
+            from: Some(100),
+            to: Some(200),
+            ..Default::default()
+        };
+
+        assert!(query.matches(&document(Some(150))));
+        assert!(!query.matches(&document(Some(50))));
+        assert!(!query.matches(&document(Some(250))));
+        assert!(query.matches(&document(None)));
+    }
+}
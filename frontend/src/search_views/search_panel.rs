@@ -0,0 +1,217 @@
+use wasm_bindgen::JsValue;
+use yew::prelude::*;
+
+use crate::{
+    account::{load_user::use_user, user_views::UserName},
+    app::App,
+    channel_views::channel_content,
+    helpers::prelude::*,
+    localization,
+    store::{self, channels::DirectChannels},
+};
+
+use super::search_index::{self, IndexedMessage, SearchQuery};
+
+pub struct SearchPanel {
+    query: SearchQuery,
+    results: Option<Vec<IndexedMessage>>,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub channel_id: i64,
+}
+
+pub enum Msg {
+    Search,
+    ChangeChannel,
+    Open(i64, i64),
+}
+
+#[derive(Properties, PartialEq, Clone)]
+struct UserOptionProps {
+    user_id: i64,
+    value: i64,
+    selected: bool,
+}
+
+impl Component for SearchPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            query: SearchQuery {
+                direct_channel_id: Some(ctx.props().channel_id),
+                ..Default::default()
+            },
+            results: None,
+        }
+    }
+
+    fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Search => self.search(),
+            Msg::ChangeChannel => {
+                self.query.direct_channel_id = Self::selected_channel();
+                self.query.author_user_id = None;
+            }
+            Msg::Open(direct_channel_id, direct_message_id) => {
+                App::open_channel(direct_channel_id);
+                channel_content::jump_to(direct_channel_id, direct_message_id);
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        let mut channels = vec![html! {
+            <option value="0" selected={self.query.direct_channel_id.is_none()}>{
+                lang.get("viewSearchAllChannels")
+            }</option>
+        }];
+        if let Some(direct_channels) = &store::get::<DirectChannels>().channels {
+            for channel in direct_channels.iter().filter(|x| !x.is_group) {
+                let selected = self.query.direct_channel_id == Some(channel.direct_channel_id);
+                channels.push(html! {
+                    <UserOption
+                        user_id={channel.user_id}
+                        value={channel.direct_channel_id}
+                        {selected}
+                    />
+                });
+            }
+        }
+
+        let mut authors = vec![html! {
+            <option value="0">{lang.get("viewSearchAnyAuthor")}</option>
+        }];
+        for author_user_id in search_index::authors(self.query.direct_channel_id) {
+            let selected = self.query.author_user_id == Some(author_user_id);
+            authors.push(html! {
+                <UserOption user_id={author_user_id} value={author_user_id} {selected} />
+            });
+        }
+
+        let results = match &self.results {
+            Some(results) if results.is_empty() => html! {
+                <p>{lang.get("viewSearchEmpty")}</p>
+            },
+            Some(results) => results
+                .iter()
+                .map(|result| {
+                    let direct_channel_id = result.direct_channel_id;
+                    let direct_message_id = result.direct_message_id;
+                    html! {
+                        <div
+                            class="search-result"
+                            onclick={ctx.link().callback(move |_| Msg::Open(direct_channel_id, direct_message_id))}
+                        >
+                            <div class="search-result-header">
+                                <UserName user_id={result.author_user_id} class="search-result-name" with_profile={true} />
+                                if let Some(created_at) = result.created_at {
+                                    <span class="search-result-date">{format_date(created_at)}</span>
+                                }
+                            </div>
+                            <span class="select">{result.text.clone()}</span>
+                        </div>
+                    }
+                })
+                .collect::<Html>(),
+            None => html! {
+                <p class="search-hint">{lang.get("viewSearchHint")}</p>
+            },
+        };
+
+        html! {
+            <div class="search-panel">
+                <h3>{lang.get("viewSearchTitle")}</h3>
+                <input
+                    id="search-text"
+                    type="text"
+                    placeholder={lang.get("viewSearchInput")}
+                    onkeydown={ctx.link().batch_callback(|e: KeyboardEvent| {
+                        (e.key() == "Enter").then_some(Msg::Search)
+                    })}
+                />
+                <select id="search-channel" onchange={ctx.link().callback(|_| Msg::ChangeChannel)}>
+                    {channels}
+                </select>
+                <select id="search-author">
+                    {authors}
+                </select>
+                <div class="search-dates">
+                    <label for="search-from">{lang.get("viewSearchFrom")}</label>
+                    <input id="search-from" type="date" />
+                    <label for="search-to">{lang.get("viewSearchTo")}</label>
+                    <input id="search-to" type="date" />
+                </div>
+                <button onclick={ctx.link().callback(|_| Msg::Search)}>{lang.get("viewSearchSubmit")}</button>
+
+                <div class="search-results">
+                    {results}
+                </div>
+            </div>
+        }
+    }
+}
+
+impl SearchPanel {
+    fn search(&mut self) {
+        let author_user_id = Select::by_id("search-author")
+            .value()
+            .parse::<i64>()
+            .unwrap_or(0);
+        let to = Self::date_value("search-to");
+
+        self.query = SearchQuery {
+            text: Input::by_id("search-text").value(),
+            direct_channel_id: Self::selected_channel(),
+            author_user_id: (author_user_id != 0).then_some(author_user_id),
+            from: Self::date_value("search-from"),
+            // Include the whole selected day.
+            to: to.map(|to| to + 24 * 60 * 60 * 1000 - 1),
+        };
+        self.results = Some(search_index::search(&self.query));
+    }
+
+    fn selected_channel() -> Option<i64> {
+        let direct_channel_id = Select::by_id("search-channel")
+            .value()
+            .parse::<i64>()
+            .unwrap_or(0);
+        (direct_channel_id != 0).then_some(direct_channel_id)
+    }
+
+    fn date_value(element_id: &str) -> Option<i64> {
+        let value = Input::by_id(element_id).value();
+        if value.is_empty() {
+            return None;
+        }
+
+        let date = js_sys::Date::parse(&value);
+        (!date.is_nan()).then_some(date as i64)
+    }
+}
+
+pub(crate) fn format_date(timestamp: i64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(timestamp as f64))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
+
+/// Option of the select named by the user, or by the value while the user is loading.
+#[function_component(UserOption)]
+fn user_option(props: &UserOptionProps) -> Html {
+    let name = match use_user(props.user_id, false).loaded() {
+        Some(user) => user.name.clone(),
+        None => props.value.to_string(),
+    };
+
+    html! {
+        <option value={props.value.to_string()} selected={props.selected}>{name}</option>
+    }
+}
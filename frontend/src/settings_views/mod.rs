@@ -1 +1,5 @@
+pub mod my_account;
+pub mod notifications_settings;
+pub mod security_settings;
+pub mod sessions_settings;
 pub mod settings;
@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use base64::{engine::general_purpose, Engine as _};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, Url};
+use yew::prelude::*;
+
+use crate::{
+    account::load_user::{self, User},
+    api::{
+        client::accounts::{self, UserUpdateRequestData},
+        ApiResponse,
+    },
+    app::App,
+    helpers::prelude::*,
+    localization,
+};
+
+const AVATAR_SIZE: u32 = 256;
+const PREVIEW_SIZE: u32 = 128;
+const MAX_ZOOM: f64 = 4.0;
+const AVATAR_UPLOAD_TIMEOUT: i32 = 120000;
+
+pub struct MyAccount {
+    user: Option<Arc<User>>,
+    status: Html,
+    avatar: Option<AvatarCrop>,
+}
+
+/// Square part of the selected image, which will be uploaded as the avatar.
+pub struct AvatarCrop {
+    image: HtmlImageElement,
+    url: String,
+    zoom: f64,
+    x: f64,
+    y: f64,
+}
+
+pub enum Msg {
+    Load(Arc<User>),
+    SetStatus(Html),
+    Save,
+    SelectAvatar,
+    LoadAvatar(HtmlImageElement, String),
+    Crop,
+    UploadAvatar,
+    CancelAvatar,
+}
+
+impl Component for MyAccount {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let callback = ctx.link().callback(Msg::Load);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(user) = load_user::get_user(App::user_id(), false).await {
+                callback.emit(user);
+            }
+        });
+
+        Self {
+            user: None,
+            status: Default::default(),
+            avatar: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Load(user) => self.user = Some(user),
+            Msg::SetStatus(status) => self.status = status,
+            Msg::Save => {
+                self.save(ctx);
+                return false;
+            }
+            Msg::SelectAvatar => {
+                Self::select_avatar(ctx);
+                return false;
+            }
+            Msg::LoadAvatar(image, url) => {
+                self.cancel_avatar();
+                self.avatar = Some(AvatarCrop {
+                    image,
+                    url,
+                    zoom: 1.0,
+                    x: 0.5,
+                    y: 0.5,
+                });
+            }
+            Msg::Crop => {
+                if let Some(avatar) = &mut self.avatar {
+                    avatar.zoom = Self::range_value("avatar-zoom", 1.0);
+                    avatar.x = Self::range_value("avatar-x", 0.5);
+                    avatar.y = Self::range_value("avatar-y", 0.5);
+                }
+            }
+            Msg::UploadAvatar => {
+                self.upload_avatar(ctx);
+                self.cancel_avatar();
+            }
+            Msg::CancelAvatar => self.cancel_avatar(),
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        let user = match &self.user {
+            Some(user) => user,
+            None => return html! { <p>{"Loading..."}</p> },
+        };
+
+        let avatar = match &self.avatar {
+            Some(avatar) => html! {
+                <div class="settings-avatar-crop">
+                    <canvas
+                        id="avatar-preview"
+                        width={PREVIEW_SIZE.to_string()}
+                        height={PREVIEW_SIZE.to_string()}
+                    />
+                    <div class="settings-avatar-crop-controls">
+                        <label for="avatar-zoom">{lang.get("viewSettingsMyAccountAvatarZoom")}</label>
+                        <input
+                            id="avatar-zoom" type="range" min="1" max={MAX_ZOOM.to_string()} step="0.01"
+                            value={avatar.zoom.to_string()} oninput={ctx.link().callback(|_| Msg::Crop)}
+                        />
+                        <label for="avatar-x">{lang.get("viewSettingsMyAccountAvatarHorizontal")}</label>
+                        <input
+                            id="avatar-x" type="range" min="0" max="1" step="0.01"
+                            value={avatar.x.to_string()} oninput={ctx.link().callback(|_| Msg::Crop)}
+                        />
+                        <label for="avatar-y">{lang.get("viewSettingsMyAccountAvatarVertical")}</label>
+                        <input
+                            id="avatar-y" type="range" min="0" max="1" step="0.01"
+                            value={avatar.y.to_string()} oninput={ctx.link().callback(|_| Msg::Crop)}
+                        />
+                        <div>
+                            <button onclick={ctx.link().callback(|_| Msg::UploadAvatar)}>{
+                                lang.get("viewSettingsMyAccountAvatarUpload")
+                            }</button>
+                            <button onclick={ctx.link().callback(|_| Msg::CancelAvatar)}>{
+                                lang.get("viewSettingsMyAccountAvatarCancel")
+                            }</button>
+                        </div>
+                    </div>
+                </div>
+            },
+            None => html! {
+                <div class="settings-avatar">
+                    <img class="settings-avatar-image" src={user.avatar_url.clone()} alt={"avatar"} />
+                    <input
+                        id="avatar-file" type="file" accept="image/png, image/jpeg, image/webp"
+                        onchange={ctx.link().callback(|_| Msg::SelectAvatar)}
+                    />
+                </div>
+            },
+        };
+
+        html! {
+            <div class="settings-page">
+                <h2>{lang.get("viewSettingsMyAccount")}</h2>
+
+                <h3>{lang.get("viewSettingsMyAccountAvatar")}</h3>
+                {avatar}
+
+                <h3>{lang.get("viewSettingsMyAccountProfile")}</h3>
+                <label for="account-name">{lang.get("viewSettingsMyAccountName")}</label>
+                <br/>
+                <input id="account-name" type="text" value={user.name.clone()} />
+                <br/><br/>
+                <label for="account-username">{lang.get("viewSettingsMyAccountUsername")}</label>
+                <br/>
+                <input id="account-username" type="text" value={user.username.clone()} />
+                <br/><br/>
+                <button onclick={ctx.link().callback(|_| Msg::Save)}>{lang.get("viewSettingsMyAccountSave")}</button>
+                {self.status.clone()}
+            </div>
+        }
+    }
+
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if let Some(avatar) = &self.avatar {
+            let canvas = Element::by_id("avatar-preview")
+                .dyn_into::<HtmlCanvasElement>()
+                .unwrap();
+            avatar.draw(&canvas, PREVIEW_SIZE);
+        }
+    }
+
+    fn destroy(&mut self, _: &Context<Self>) {
+        self.cancel_avatar();
+    }
+}
+
+impl MyAccount {
+    fn save(&self, ctx: &Context<Self>) {
+        let status = ctx.link().callback(Msg::SetStatus);
+        let callback = ctx.link().callback(Msg::Load);
+
+        accounts::update_user(&UserUpdateRequestData {
+            name: Input::by_id("account-name").value().trim().to_owned(),
+            username: Input::by_id("account-username").value().trim().to_owned(),
+        })
+        .send(move |r| match r {
+            ApiResponse::Ok(user) => {
+                load_user::reload_user(user.clone());
+                callback.emit(Arc::new(user));
+                status.emit(Status::with_ok("viewSettingsMyAccountSaved"));
+            }
+            ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+        });
+    }
+
+    fn select_avatar(ctx: &Context<Self>) {
+        let file_input = Input::by_id("avatar-file");
+        let file = match file_input.files().and_then(|x| x.get(0)) {
+            Some(file) => file,
+            None => return,
+        };
+        file_input.set_value("");
+
+        let callback = ctx
+            .link()
+            .callback(|(image, url)| Msg::LoadAvatar(image, url));
+        let status = ctx.link().callback(Msg::SetStatus);
+        wasm_bindgen_futures::spawn_local(async move {
+            let bytes = File::to_bytes_without_exif(&file).await;
+            let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes.as_slice()));
+            let blob = Blob::new_with_u8_array_sequence(&parts).unwrap();
+            let url = Url::create_object_url_with_blob(&blob).unwrap();
+
+            let image = HtmlImageElement::new().unwrap();
+            let promise = js_sys::Promise::new(&mut |resolve, reject| {
+                image.set_onload(Some(&resolve));
+                image.set_onerror(Some(&reject));
+            });
+            image.set_src(&url);
+
+            match JsFuture::from(promise).await {
+                Ok(_) => callback.emit((image, url)),
+                Err(_) => {
+                    Url::revoke_object_url(&url).unwrap();
+                    status.emit(Status::with_err_key("viewSettingsMyAccountAvatarInvalid"));
+                }
+            }
+        });
+    }
+
+    fn upload_avatar(&self, ctx: &Context<Self>) {
+        let avatar = match &self.avatar {
+            Some(avatar) => avatar,
+            None => return,
+        };
+
+        let canvas = WebPage::document()
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into::<HtmlCanvasElement>()
+            .unwrap();
+        canvas.set_width(AVATAR_SIZE);
+        canvas.set_height(AVATAR_SIZE);
+        avatar.draw(&canvas, AVATAR_SIZE);
+
+        let data_url = canvas.to_data_url_with_type("image/png").unwrap();
+        let bytes = general_purpose::STANDARD
+            .decode(data_url.split(',').nth(1).unwrap_or(""))
+            .unwrap();
+
+        let status = ctx.link().callback(Msg::SetStatus);
+        let callback = ctx.link().callback(Msg::Load);
+        accounts::put_avatar(bytes)
+            .timeout(AVATAR_UPLOAD_TIMEOUT)
+            .send(move |r| match r {
+                ApiResponse::Ok(user) => {
+                    load_user::reload_user(user.clone());
+                    callback.emit(Arc::new(user));
+                    status.emit(Status::with_ok("viewSettingsMyAccountAvatarSaved"));
+                }
+                ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+            });
+    }
+
+    fn cancel_avatar(&mut self) {
+        if let Some(avatar) = self.avatar.take() {
+            Url::revoke_object_url(&avatar.url).unwrap();
+        }
+    }
+
+    fn range_value(element_id: &str, default: f64) -> f64 {
+        Input::by_id(element_id).value().parse().unwrap_or(default)
+    }
+}
+
+impl AvatarCrop {
+    fn draw(&self, canvas: &HtmlCanvasElement, size: u32) {
+        let context = canvas
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()
+            .unwrap();
+
+        let width = self.image.natural_width() as f64;
+        let height = self.image.natural_height() as f64;
+        let crop = width.min(height) / self.zoom.clamp(1.0, MAX_ZOOM);
+        let sx = (width - crop) * self.x.clamp(0.0, 1.0);
+        let sy = (height - crop) * self.y.clamp(0.0, 1.0);
+
+        let size = size as f64;
+        context.clear_rect(0.0, 0.0, size, size);
+        if context
+            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                &self.image,
+                sx,
+                sy,
+                crop,
+                crop,
+                0.0,
+                0.0,
+                size,
+                size,
+            )
+            .is_err()
+        {
+            log::error!("Unable to draw avatar.");
+        }
+    }
+}
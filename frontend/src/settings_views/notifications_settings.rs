@@ -0,0 +1,163 @@
+use web_sys::NotificationPermission;
+use yew::prelude::*;
+
+use crate::{
+    account::user_views::UserName,
+    channel_views::channel_notifications,
+    localization,
+    notifications::{self, ChannelNotificationSettings, NotificationLevel, NotificationSettings},
+    store::{self, channels::DirectChannels},
+};
+
+pub struct NotificationsSettings {
+    settings: NotificationSettings,
+    permission: NotificationPermission,
+}
+
+pub enum Msg {
+    ToggleEnabled,
+    TogglePrivacyMode,
+    Reset(i64),
+    RequestPermission,
+    SetPermission(NotificationPermission),
+}
+
+impl Component for NotificationsSettings {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: &Context<Self>) -> Self {
+        Self {
+            settings: notifications::settings(),
+            permission: notifications::permission(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ToggleEnabled => {
+                self.settings.enabled = !self.settings.enabled;
+                if self.settings.enabled && self.permission == NotificationPermission::Default {
+                    ctx.link().send_message(Msg::RequestPermission);
+                }
+            }
+            Msg::TogglePrivacyMode => self.settings.privacy_mode = !self.settings.privacy_mode,
+            Msg::Reset(direct_channel_id) => {
+                self.settings.channels.remove(&direct_channel_id);
+            }
+            Msg::RequestPermission => {
+                ctx.link().send_future(async {
+                    Msg::SetPermission(notifications::request_permission().await)
+                });
+                return false;
+            }
+            Msg::SetPermission(permission) => {
+                self.permission = permission;
+                return true;
+            }
+        };
+
+        notifications::set_settings(&self.settings);
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        let permission = match self.permission {
+            NotificationPermission::Granted => html! {},
+            NotificationPermission::Default => html! {
+                <div class="settings-notice">
+                    <p>{lang.get("viewSettingsNotificationsPermissionDefault")}</p>
+                    <button onclick={ctx.link().callback(|_| Msg::RequestPermission)}>{
+                        lang.get("viewSettingsNotificationsPermissionRequest")
+                    }</button>
+                </div>
+            },
+            _ => html! {
+                <div class="settings-notice">
+                    <p>{lang.get("viewSettingsNotificationsPermissionDenied")}</p>
+                </div>
+            },
+        };
+
+        let mut channels = self
+            .settings
+            .channels
+            .iter()
+            .filter(|x| !x.1.is_default())
+            .map(|x| (*x.0, *x.1))
+            .collect::<Vec<(i64, ChannelNotificationSettings)>>();
+        channels.sort_by_key(|x| x.0);
+        let channels = match channels.is_empty() {
+            true => html! { <p>{lang.get("viewSettingsNotificationsNoChannels")}</p> },
+            false => channels
+                .into_iter()
+                .map(|(direct_channel_id, settings)| {
+                    let level = match settings.level {
+                        NotificationLevel::All => lang.get("viewChannelNotificationsAll"),
+                        NotificationLevel::Mentions => lang.get("viewChannelNotificationsMentions"),
+                        NotificationLevel::Nothing => lang.get("viewChannelNotificationsNothing"),
+                    };
+                    let muted = match settings.is_muted() {
+                        true => html! { <span>{channel_notifications::muted_until_text(settings.muted_until)}</span> },
+                        false => html! {},
+                    };
+
+                    html! {
+                        <div class="settings-channel">
+                            {channel_name_view(direct_channel_id)}
+                            <span class="settings-hint">{level}</span>
+                            {muted}
+                            <button onclick={ctx.link().callback(move |_| Msg::Reset(direct_channel_id))}>{
+                                lang.get("viewSettingsNotificationsReset")
+                            }</button>
+                        </div>
+                    }
+                })
+                .collect::<Html>(),
+        };
+
+        html! {
+            <div class="settings-page">
+                <h2>{lang.get("viewSettingsNotifications")}</h2>
+                {permission}
+
+                <label class="settings-checkbox">
+                    <input
+                        type="checkbox"
+                        checked={self.settings.enabled}
+                        onchange={ctx.link().callback(|_| Msg::ToggleEnabled)}
+                    />
+                    {lang.get("viewSettingsNotificationsEnabled")}
+                </label>
+                <label class="settings-checkbox">
+                    <input
+                        type="checkbox"
+                        checked={self.settings.privacy_mode}
+                        onchange={ctx.link().callback(|_| Msg::TogglePrivacyMode)}
+                    />
+                    {lang.get("viewSettingsNotificationsPrivacyMode")}
+                </label>
+                <p class="settings-hint">{lang.get("viewSettingsNotificationsDoNotDisturbHint")}</p>
+
+                <h3>{lang.get("viewSettingsNotificationsChannels")}</h3>
+                {channels}
+            </div>
+        }
+    }
+}
+
+fn channel_name_view(direct_channel_id: i64) -> Html {
+    let user_id = store::get::<DirectChannels>()
+        .find(direct_channel_id)
+        .filter(|x| !x.is_group)
+        .map(|x| x.user_id);
+
+    match user_id {
+        Some(user_id) => html! {
+            <UserName {user_id} />
+        },
+        None => html! { <span>{direct_channel_id}</span> },
+    }
+}
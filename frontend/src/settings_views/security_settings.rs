@@ -0,0 +1,297 @@
+use qrcode::{Color, QrCode};
+use yew::prelude::*;
+
+use crate::{
+    account::registration::{self, MIN_PASSWORD_STRENGTH},
+    api::{
+        client::accounts::{self, TwoFactorCodeRequestData, TwoFactorSetupResponseData},
+        AbortHandle, ApiResponse,
+    },
+    direct_messages_views::encryption,
+    helpers::prelude::*,
+    localization,
+};
+
+const QR_CODE_QUIET_ZONE: usize = 4;
+
+pub struct SecuritySettings {
+    two_factor_enabled: Option<bool>,
+    setup: Option<TwoFactorSetupResponseData>,
+    recovery_codes: Option<Vec<String>>,
+    status: Html,
+    backup_status: Html,
+    abort: AbortHandle,
+}
+
+pub enum Msg {
+    Load(bool),
+    SetStatus(Html),
+    StartSetup,
+    Setup(TwoFactorSetupResponseData),
+    CancelSetup,
+    Enable,
+    Enabled(Vec<String>),
+    ConfirmRecoveryCodes,
+    Disable,
+    SetBackupStatus(Html),
+    ExportBackup,
+}
+
+impl Component for SecuritySettings {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let abort = AbortHandle::default();
+        let callback = ctx.link().callback(Msg::Load);
+        accounts::get_two_factor()
+            .abort_with(&abort)
+            .send(move |r| match r {
+                ApiResponse::Ok(r) => callback.emit(r.enabled),
+                ApiResponse::BadRequest(_) => {
+                    log::error!("Failed to load two-factor authentication.")
+                }
+            });
+
+        Self {
+            two_factor_enabled: None,
+            setup: None,
+            recovery_codes: None,
+            status: Status::default_html(),
+            backup_status: Status::default_html(),
+            abort,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Load(enabled) => self.two_factor_enabled = Some(enabled),
+            Msg::SetStatus(status) => self.status = status,
+            Msg::StartSetup => {
+                let callback = ctx.link().callback(Msg::Setup);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accounts::setup_two_factor()
+                    .abort_with(&self.abort)
+                    .send(move |r| match r {
+                        ApiResponse::Ok(r) => callback.emit(r),
+                        ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+                    });
+                return false;
+            }
+            Msg::Setup(setup) => {
+                self.setup = Some(setup);
+                self.status = Status::default_html();
+            }
+            Msg::CancelSetup => {
+                self.setup = None;
+                self.status = Status::default_html();
+            }
+            Msg::Enable => {
+                let code = match Self::code() {
+                    Some(code) => code,
+                    None => return false,
+                };
+
+                let callback = ctx.link().callback(Msg::Enabled);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accounts::enable_two_factor(&TwoFactorCodeRequestData { code })
+                    .abort_with(&self.abort)
+                    .send(move |r| match r {
+                        ApiResponse::Ok(r) => callback.emit(r.recovery_codes),
+                        ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+                    });
+                return false;
+            }
+            Msg::Enabled(recovery_codes) => {
+                self.two_factor_enabled = Some(true);
+                self.setup = None;
+                self.recovery_codes = Some(recovery_codes);
+                self.status = Status::default_html();
+            }
+            Msg::ConfirmRecoveryCodes => self.recovery_codes = None,
+            Msg::Disable => {
+                let code = match Self::code() {
+                    Some(code) => code,
+                    None => return false,
+                };
+
+                let callback = ctx.link().callback(Msg::Load);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accounts::disable_two_factor(&TwoFactorCodeRequestData { code })
+                    .abort_with(&self.abort)
+                    .send(move |r| match r {
+                        ApiResponse::Ok(_) => {
+                            callback.emit(false);
+                            status.emit(Status::with_ok("viewSettingsSecurityTwoFactorDisabled"));
+                        }
+                        ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+                    });
+                return false;
+            }
+            Msg::SetBackupStatus(status) => self.backup_status = status,
+            Msg::ExportBackup => {
+                self.export_backup(ctx);
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        html! {
+            <div class="settings-page">
+                <h2>{lang.get("viewSettingsSecurity")}</h2>
+
+                <h3>{lang.get("viewSettingsSecurityTwoFactor")}</h3>
+                <p class="settings-hint">{lang.get("viewSettingsSecurityTwoFactorHint")}</p>
+                {self.two_factor_view(ctx)}
+                {self.status.clone()}
+
+                <h3>{lang.get("viewSettingsSecurityKeyBackup")}</h3>
+                <p class="settings-hint">{lang.get("viewSettingsSecurityKeyBackupHint")}</p>
+                <input
+                    id="key-backup-passphrase" type="password"
+                    placeholder={lang.get("viewSettingsSecurityKeyBackupPassphrase")}
+                />
+                <input
+                    id="key-backup-confirm-passphrase" type="password"
+                    placeholder={lang.get("viewSettingsSecurityKeyBackupConfirmPassphrase")}
+                />
+                <button onclick={ctx.link().callback(|_| Msg::ExportBackup)}>{
+                    lang.get("viewSettingsSecurityKeyBackupExport")
+                }</button>
+                {self.backup_status.clone()}
+            </div>
+        }
+    }
+}
+
+impl SecuritySettings {
+    fn two_factor_view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        if let Some(recovery_codes) = &self.recovery_codes {
+            return html! {
+                <div class="settings-notice">
+                    <p>{lang.get("viewSettingsSecurityRecoveryCodesHint")}</p>
+                    <div class="settings-recovery-codes select">
+                        {
+                            recovery_codes.iter().map(|code| html! {
+                                <code>{code.clone()}</code>
+                            }).collect::<Html>()
+                        }
+                    </div>
+                    <button onclick={ctx.link().callback(|_| Msg::ConfirmRecoveryCodes)}>{
+                        lang.get("viewSettingsSecurityRecoveryCodesConfirm")
+                    }</button>
+                </div>
+            };
+        }
+
+        if let Some(setup) = &self.setup {
+            return html! {
+                <div class="settings-two-factor-setup">
+                    <p>{lang.get("viewSettingsSecurityTwoFactorScan")}</p>
+                    {qr_code_view(&setup.provisioning_uri)}
+                    <p class="settings-hint">{lang.get("viewSettingsSecurityTwoFactorSecret")}</p>
+                    <code class="select">{setup.secret.clone()}</code>
+                    <br/><br/>
+                    <input
+                        id="two-factor-code" type="text" autocomplete="one-time-code"
+                        placeholder={lang.get("viewSettingsSecurityTwoFactorCode")}
+                    />
+                    <button onclick={ctx.link().callback(|_| Msg::Enable)}>{
+                        lang.get("viewSettingsSecurityTwoFactorEnable")
+                    }</button>
+                    <button onclick={ctx.link().callback(|_| Msg::CancelSetup)}>{
+                        lang.get("viewSettingsSecurityTwoFactorCancel")
+                    }</button>
+                </div>
+            };
+        }
+
+        match self.two_factor_enabled {
+            None => html! { <p>{"Loading..."}</p> },
+            Some(true) => html! { <>
+                <p>{lang.get("viewSettingsSecurityTwoFactorActive")}</p>
+                <input
+                    id="two-factor-code" type="text" autocomplete="one-time-code"
+                    placeholder={lang.get("viewSettingsSecurityTwoFactorCodeOrRecovery")}
+                />
+                <button onclick={ctx.link().callback(|_| Msg::Disable)}>{
+                    lang.get("viewSettingsSecurityTwoFactorDisable")
+                }</button>
+            </> },
+            Some(false) => html! {
+                <button onclick={ctx.link().callback(|_| Msg::StartSetup)}>{
+                    lang.get("viewSettingsSecurityTwoFactorSetup")
+                }</button>
+            },
+        }
+    }
+
+    fn export_backup(&self, ctx: &Context<Self>) {
+        let passphrase = Input::by_id("key-backup-passphrase").value();
+        let confirm_passphrase = Input::by_id("key-backup-confirm-passphrase").value();
+
+        let status = ctx.link().callback(Msg::SetBackupStatus);
+        if passphrase != confirm_passphrase {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordMismatch",
+            ));
+            return;
+        }
+        if registration::password_strength(&passphrase) < MIN_PASSWORD_STRENGTH {
+            status.emit(Status::with_err_key(
+                "viewAccountRegistrationPasswordTooWeak",
+            ));
+            return;
+        }
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let backup = encryption::export_key_backup(&passphrase).await;
+            File::download("arlekin-key-backup.bin", &backup);
+            status.emit(Status::with_ok("viewSettingsSecurityKeyBackupExported"));
+        });
+    }
+
+    fn code() -> Option<String> {
+        let code = Input::by_id("two-factor-code").value().replace(' ', "");
+        match code.is_empty() {
+            true => None,
+            false => Some(code),
+        }
+    }
+}
+
+/// Renders the QR code as SVG, so the secret is never sent to any third party service.
+fn qr_code_view(data: &str) -> Html {
+    let code = match QrCode::new(data) {
+        Ok(code) => code,
+        Err(_) => return html! {},
+    };
+
+    let width = code.width();
+    let mut path = String::new();
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let x = i % width + QR_CODE_QUIET_ZONE;
+            let y = i / width + QR_CODE_QUIET_ZONE;
+            path.push_str(&format!("M{x},{y}h1v1h-1z"));
+        }
+    }
+
+    let size = width + QR_CODE_QUIET_ZONE * 2;
+    html! {
+        <svg
+            class="settings-qr-code"
+            viewBox={format!("0 0 {size} {size}")}
+            shape-rendering="crispEdges"
+        >
+            <rect width={size.to_string()} height={size.to_string()} fill="white" />
+            <path d={path} fill="black" />
+        </svg>
+    }
+}
@@ -0,0 +1,162 @@
+use yew::prelude::*;
+
+use crate::{
+    api::{
+        client::accounts::{self, SessionIdRequestData, SessionResponseData},
+        AbortHandle, ApiResponse, Platform,
+    },
+    helpers::prelude::*,
+    localization,
+    search_views::search_panel::format_date,
+};
+
+pub struct SessionsSettings {
+    sessions: Option<Vec<SessionResponseData>>,
+    status: Html,
+    abort: AbortHandle,
+}
+
+pub enum Msg {
+    Load(Vec<SessionResponseData>),
+    Reload,
+    SetStatus(Html),
+    Revoke(i64),
+    RevokeOthers,
+}
+
+impl Component for SessionsSettings {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let abort = AbortHandle::default();
+        Self::load(ctx, &abort);
+        Self {
+            sessions: None,
+            status: Status::default_html(),
+            abort,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Load(mut sessions) => {
+                // Current session first, then the most recently used.
+                sessions.sort_by(|a, b| {
+                    b.is_current
+                        .cmp(&a.is_current)
+                        .then(b.last_seen.cmp(&a.last_seen))
+                });
+                self.sessions = Some(sessions);
+            }
+            Msg::Reload => {
+                Self::load(ctx, &self.abort);
+                return false;
+            }
+            Msg::SetStatus(status) => self.status = status,
+            Msg::Revoke(session_id) => {
+                let reload = ctx.link().callback(|_| Msg::Reload);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accounts::revoke_session(&SessionIdRequestData { session_id })
+                    .abort_with(&self.abort)
+                    .send(move |r| match r {
+                        ApiResponse::Ok(_) => reload.emit(()),
+                        ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+                    });
+                return false;
+            }
+            Msg::RevokeOthers => {
+                let reload = ctx.link().callback(|_| Msg::Reload);
+                let status = ctx.link().callback(Msg::SetStatus);
+                accounts::revoke_other_sessions()
+                    .abort_with(&self.abort)
+                    .send(move |r| match r {
+                        ApiResponse::Ok(_) => {
+                            reload.emit(());
+                            status.emit(Status::with_ok("viewSettingsSessionsRevokedOthers"));
+                        }
+                        ApiResponse::BadRequest(err) => status.emit(Status::with_err(err)),
+                    });
+                return false;
+            }
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        let sessions = match &self.sessions {
+            Some(sessions) => sessions
+                .iter()
+                .map(|session| Self::session_view(ctx, session))
+                .collect::<Html>(),
+            None => html! { <p>{"Loading..."}</p> },
+        };
+        let has_others = self
+            .sessions
+            .as_ref()
+            .map(|x| x.iter().any(|session| !session.is_current))
+            .unwrap_or(false);
+
+        html! {
+            <div class="settings-page">
+                <h2>{lang.get("viewSettingsSessions")}</h2>
+                <p class="settings-hint">{lang.get("viewSettingsSessionsHint")}</p>
+                {sessions}
+                if has_others {
+                    <button onclick={ctx.link().callback(|_| Msg::RevokeOthers)}>{
+                        lang.get("viewSettingsSessionsRevokeOthers")
+                    }</button>
+                }
+                {self.status.clone()}
+            </div>
+        }
+    }
+}
+
+impl SessionsSettings {
+    fn load(ctx: &Context<Self>, abort: &AbortHandle) {
+        let callback = ctx.link().callback(Msg::Load);
+        accounts::get_sessions()
+            .abort_with(abort)
+            .send(move |r| match r {
+                ApiResponse::Ok(r) => callback.emit(r.sessions),
+                ApiResponse::BadRequest(_) => log::error!("Failed to load sessions."),
+            });
+    }
+
+    fn session_view(ctx: &Context<Self>, session: &SessionResponseData) -> Html {
+        let lang = localization::get_language();
+        let platform = match session.platform {
+            Platform::Native => lang.get("viewSettingsSessionsPlatformNative"),
+        };
+
+        let action = match session.is_current {
+            true => html! {
+                <span class="settings-hint">{lang.get("viewSettingsSessionsCurrent")}</span>
+            },
+            false => {
+                let session_id = session.session_id;
+                html! {
+                    <button onclick={ctx.link().callback(move |_| Msg::Revoke(session_id))}>{
+                        lang.get("viewSettingsSessionsRevoke")
+                    }</button>
+                }
+            }
+        };
+
+        html! {
+            <div class="settings-session">
+                <div>
+                    <p class="settings-session-device">{session.device.clone()}</p>
+                    <p class="settings-hint">
+                        {format!("{platform} · {} · ", session.ip_address)}
+                        {lang.get("viewSettingsSessionsLastSeen").replace("{date}", &format_date(session.last_seen))}
+                    </p>
+                </div>
+                {action}
+            </div>
+        }
+    }
+}
@@ -4,27 +4,94 @@ use yew_icons::{Icon, IconId};
 use crate::{
     app::App,
     localization,
-    route::{self, Route},
+    route::{self, Route, SettingsPage},
 };
 
-#[function_component(Settings)]
-pub fn settings() -> Html {
-    let lang = localization::get_language();
-    html! { <>
-        <route::Router route={Route::Settings} />
+use super::{
+    my_account::MyAccount, notifications_settings::NotificationsSettings,
+    security_settings::SecuritySettings, sessions_settings::SessionsSettings,
+};
 
-        <div class="settings-container">
-            <div class="settings-inner">
-                <div class="settings-navigator">
-                    <button onclick={Callback::from(|_| App::logout())}>{lang.get("viewSettingsLogoutButton")}</button>
-                </div>
-                <div class="settings-content">
+pub struct Settings {}
 
-                </div>
-                <div class="settings-exit">
-                    <Icon onclick={Callback::from(|_| App::display_settings(false))} icon_id={IconId::BootstrapXCircle}/>
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub page: SettingsPage,
+}
+
+pub enum Msg {
+    Select(SettingsPage),
+}
+
+impl Component for Settings {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Select(page) => {
+                if ctx.props().page != page {
+                    route::navigate(Route::settings(page));
+                }
+            }
+        };
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let lang = localization::get_language();
+
+        let content = match ctx.props().page {
+            SettingsPage::MyAccount => html! { <MyAccount /> },
+            SettingsPage::Security => html! { <SecuritySettings /> },
+            SettingsPage::Sessions => html! { <SessionsSettings /> },
+            SettingsPage::Notifications => html! { <NotificationsSettings /> },
+        };
+
+        html! { <>
+            <div class="settings-container">
+                <div class="settings-inner">
+                    <div class="settings-navigator">
+                        <button
+                            onclick={ctx.link().callback(|_| Msg::Select(SettingsPage::MyAccount))}
+                            class={Self::get_selected(ctx, SettingsPage::MyAccount)}
+                        >{lang.get("viewSettingsMyAccount")}</button>
+                        <button
+                            onclick={ctx.link().callback(|_| Msg::Select(SettingsPage::Security))}
+                            class={Self::get_selected(ctx, SettingsPage::Security)}
+                        >{lang.get("viewSettingsSecurity")}</button>
+                        <button
+                            onclick={ctx.link().callback(|_| Msg::Select(SettingsPage::Sessions))}
+                            class={Self::get_selected(ctx, SettingsPage::Sessions)}
+                        >{lang.get("viewSettingsSessions")}</button>
+                        <button
+                            onclick={ctx.link().callback(|_| Msg::Select(SettingsPage::Notifications))}
+                            class={Self::get_selected(ctx, SettingsPage::Notifications)}
+                        >{lang.get("viewSettingsNotifications")}</button>
+                        <button onclick={Callback::from(|_| App::logout())}>{lang.get("viewSettingsLogoutButton")}</button>
+                    </div>
+                    <div class="settings-content">
+                        {content}
+                    </div>
+                    <div class="settings-exit">
+                        <Icon onclick={Callback::from(|_| App::display_settings(false))} icon_id={IconId::BootstrapXCircle}/>
+                    </div>
                 </div>
             </div>
-        </div>
-    </> }
+        </> }
+    }
+}
+
+impl Settings {
+    fn get_selected(ctx: &Context<Self>, expected: SettingsPage) -> String {
+        match ctx.props().page == expected {
+            true => "button-selected",
+            false => "",
+        }
+        .to_owned()
+    }
 }
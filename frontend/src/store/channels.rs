@@ -0,0 +1,111 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use crate::api::client::channels::{DirectChannelResponseData, DirectChannelsLoadResponseData};
+
+use super::{Reducer, Slice};
+
+/// Direct channels displayed by the navigator, with unread counts updated locally.
+#[derive(Clone, Default)]
+pub struct DirectChannels {
+    /// Channels ordered by recent activity, `None` until they are loaded.
+    pub channels: Option<Vec<DirectChannelResponseData>>,
+    /// Response from which `channels` were made, before they were updated locally.
+    loaded: Option<Arc<DirectChannelsLoadResponseData>>,
+}
+
+impl Slice for DirectChannels {}
+
+impl DirectChannels {
+    pub fn find(&self, direct_channel_id: i64) -> Option<&DirectChannelResponseData> {
+        self.channels
+            .as_ref()?
+            .iter()
+            .find(|x| x.direct_channel_id == direct_channel_id)
+    }
+
+    /// Whether the channels were already made from the response.
+    pub fn is_loaded_from(&self, response: &Arc<DirectChannelsLoadResponseData>) -> bool {
+        matches!(&self.loaded, Some(x) if Arc::ptr_eq(x, response))
+    }
+}
+
+pub enum ChannelsAction {
+    Loaded(Arc<DirectChannelsLoadResponseData>),
+    /// Moves the channel to the top of the list.
+    Activity(i64),
+    AddPings {
+        direct_channel_id: i64,
+        pings_to_add: i64,
+        new_last_read_message_id: i64,
+    },
+    AddMention(i64),
+}
+
+impl Reducer<DirectChannels> for ChannelsAction {
+    fn apply(self, state: &mut DirectChannels) {
+        match self {
+            ChannelsAction::Loaded(loaded) => {
+                let mut channels = loaded.direct_channels.clone();
+                channels.sort_by_key(|x| Reverse(x.recent_activity));
+
+                // Mentions are counted only on this device.
+                for element in &mut channels {
+                    if let Some(old) = state.find(element.direct_channel_id) {
+                        element.mention_count = old.mention_count.min(element.unread_count);
+                    }
+                }
+
+                state.channels = Some(channels);
+                state.loaded = Some(loaded);
+            }
+            ChannelsAction::Activity(direct_channel_id) => {
+                move_to_top(state, direct_channel_id);
+            }
+            ChannelsAction::AddPings {
+                direct_channel_id,
+                pings_to_add,
+                new_last_read_message_id,
+            } => {
+                let element = match state.channels.as_mut().and_then(|channels| {
+                    channels
+                        .iter_mut()
+                        .find(|x| x.direct_channel_id == direct_channel_id)
+                }) {
+                    Some(element) => element,
+                    None => return,
+                };
+
+                element.unread_count = (element.unread_count + pings_to_add).max(0);
+                if pings_to_add < 0 {
+                    element.last_read_direct_message_id = new_last_read_message_id;
+                    element.mention_count = element.mention_count.min(element.unread_count);
+                }
+
+                if pings_to_add > 0 {
+                    move_to_top(state, direct_channel_id);
+                }
+            }
+            ChannelsAction::AddMention(direct_channel_id) => {
+                if let Some(element) = state.channels.as_mut().and_then(|channels| {
+                    channels
+                        .iter_mut()
+                        .find(|x| x.direct_channel_id == direct_channel_id)
+                }) {
+                    element.mention_count += 1;
+                }
+            }
+        }
+    }
+}
+
+fn move_to_top(state: &mut DirectChannels, direct_channel_id: i64) {
+    if let Some(channels) = &mut state.channels {
+        if let Some(index) = channels
+            .iter()
+            .position(|x| x.direct_channel_id == direct_channel_id)
+        {
+            let element = channels.remove(index);
+            channels.insert(0, element);
+        }
+    }
+}
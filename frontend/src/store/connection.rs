@@ -0,0 +1,15 @@
+use super::Slice;
+
+#[derive(Clone, PartialEq)]
+pub struct Connection {
+    /// Whether the last request reached the API.
+    pub is_connected: bool,
+}
+
+impl Slice for Connection {}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Self { is_connected: true }
+    }
+}
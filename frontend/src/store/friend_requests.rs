@@ -0,0 +1,12 @@
+use crate::api::client::accounts::FriendRequestsLoadResponseData;
+
+use super::Slice;
+
+/// Friend requests which were not accepted or rejected yet.
+#[derive(Clone, Default)]
+pub struct PendingFriendRequests {
+    /// `None` until the requests are loaded.
+    pub requests: Option<FriendRequestsLoadResponseData>,
+}
+
+impl Slice for PendingFriendRequests {}
@@ -0,0 +1,230 @@
+//! Application state shared between components. The state is split into slices, each of them is
+//! changed only by dispatching an action to it, and components subscribe to the slices which they
+//! display. Events are messages without a state, which are delivered to the current listeners.
+//!
+//! The application runs on a single thread, so the store lives in thread local storage.
+
+pub mod channels;
+pub mod connection;
+pub mod friend_requests;
+pub mod session;
+pub mod users;
+
+use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use yew::prelude::*;
+
+thread_local! {
+    static ENTRIES: RefCell<HashMap<TypeId, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Part of the state which is changed and observed independently of other slices.
+pub trait Slice: Clone + Default + 'static {}
+
+/// Action which changes the state of the slice.
+pub trait Reducer<S> {
+    fn apply(self, state: &mut S);
+}
+
+impl<S, F> Reducer<S> for F
+where
+    F: FnOnce(&mut S),
+{
+    fn apply(self, state: &mut S) {
+        self(state)
+    }
+}
+
+/// Subscription to a slice or an event, which is cancelled when dropped.
+pub struct Subscription {
+    cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+struct Listeners<T> {
+    callbacks: RefCell<BTreeMap<u64, Callback<T>>>,
+    next_id: Cell<u64>,
+}
+
+impl<T: 'static> Listeners<T> {
+    fn add(self: &Rc<Self>, callback: Callback<T>) -> Subscription {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.callbacks.borrow_mut().insert(id, callback);
+
+        let listeners = Rc::downgrade(self);
+        Subscription {
+            cancel: Some(Box::new(move || {
+                if let Some(listeners) = listeners.upgrade() {
+                    listeners.callbacks.borrow_mut().remove(&id);
+                }
+            })),
+        }
+    }
+
+    /// Callbacks are collected first, so listeners are able to subscribe or dispatch again.
+    fn emit(&self, value: T)
+    where
+        T: Clone,
+    {
+        let callbacks = self
+            .callbacks
+            .borrow()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for callback in callbacks {
+            callback.emit(value.clone());
+        }
+    }
+}
+
+impl<T> Default for Listeners<T> {
+    fn default() -> Self {
+        Self {
+            callbacks: RefCell::default(),
+            next_id: Cell::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entry<S> {
+    state: RefCell<Rc<S>>,
+    listeners: Rc<Listeners<Rc<S>>>,
+}
+
+fn entry<E: Default + 'static>() -> Rc<E> {
+    ENTRIES.with(|entries| {
+        entries
+            .borrow_mut()
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Rc::new(E::default()))
+            .clone()
+            .downcast::<E>()
+            .unwrap()
+    })
+}
+
+/// Returns the current state of the slice.
+pub fn get<S: Slice>() -> Rc<S> {
+    entry::<Entry<S>>().state.borrow().clone()
+}
+
+/// Applies the action to the slice and notifies its subscribers.
+pub fn dispatch<S: Slice, A: Reducer<S>>(action: A) {
+    let entry = entry::<Entry<S>>();
+    let state = {
+        let mut state = entry.state.borrow_mut();
+        action.apply(Rc::make_mut(&mut state));
+        state.clone()
+    };
+    entry.listeners.emit(state);
+}
+
+/// Replaces the slice with its default state, used when the user logs out.
+pub fn reset<S: Slice>() {
+    dispatch(|state: &mut S| *state = S::default());
+}
+
+/// Calls the callback with the new state whenever the slice changes.
+pub fn subscribe<S: Slice>(callback: Callback<Rc<S>>) -> Subscription {
+    entry::<Entry<S>>().listeners.add(callback)
+}
+
+/// Delivers the event to the current listeners of its type.
+pub fn emit<E: Clone + 'static>(event: E) {
+    entry::<Listeners<E>>().emit(event);
+}
+
+/// Calls the callback with every emitted event of the type.
+pub fn listen<E: Clone + 'static>(callback: Callback<E>) -> Subscription {
+    entry::<Listeners<E>>().add(callback)
+}
+
+/// Returns the value selected from the slice, and renders the component again only when the
+/// selected value changes. Selector is replaced when the dependencies change.
+#[hook]
+pub fn use_selector<S, T, F, D>(selector: F, deps: D) -> T
+where
+    S: Slice,
+    T: Clone + PartialEq + 'static,
+    F: Fn(&S) -> T + 'static,
+    D: PartialEq + 'static,
+{
+    let selector = Rc::new(selector);
+    let value = {
+        let selector = selector.clone();
+        use_state_eq(move || selector(&get::<S>()))
+    };
+    {
+        let setter = value.setter();
+        use_effect_with_deps(
+            move |_| {
+                setter.set(selector(&get::<S>()));
+                let subscription =
+                    subscribe::<S>(Callback::from(move |x: Rc<S>| setter.set(selector(&x))));
+                move || drop(subscription)
+            },
+            deps,
+        );
+    }
+    (*value).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Counter {
+        value: i32,
+    }
+
+    impl Slice for Counter {}
+
+    #[wasm_bindgen_test]
+    fn subscribers_receive_state_until_dropped() {
+        reset::<Counter>();
+        let received = Rc::new(Cell::new(0));
+        let subscription = {
+            let received = received.clone();
+            subscribe::<Counter>(Callback::from(move |x: Rc<Counter>| received.set(x.value)))
+        };
+
+        dispatch(|x: &mut Counter| x.value += 2);
+        assert_eq!(received.get(), 2);
+        assert_eq!(get::<Counter>().value, 2);
+
+        drop(subscription);
+        dispatch(|x: &mut Counter| x.value += 2);
+        assert_eq!(received.get(), 2);
+
+        reset::<Counter>();
+        assert_eq!(get::<Counter>().value, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn listeners_may_dispatch_while_notified() {
+        let _subscription = listen::<i32>(Callback::from(|x| {
+            dispatch(move |counter: &mut Counter| counter.value = x)
+        }));
+
+        emit(5);
+        assert_eq!(get::<Counter>().value, 5);
+    }
+}
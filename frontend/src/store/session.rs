@@ -0,0 +1,46 @@
+use crate::route::Route;
+
+use super::{Reducer, Slice};
+
+/// Logged in user and the view which is opened.
+#[derive(Clone, Default, PartialEq)]
+pub struct Session {
+    /// Zero while the user is not logged in.
+    pub user_id: i64,
+    pub is_settings_displayed: bool,
+    /// Zero while friends are displayed instead of a direct channel. Channel stays opened while
+    /// settings are displayed.
+    pub opened_channel: i64,
+}
+
+impl Slice for Session {}
+
+impl Session {
+    pub fn is_logged_in(&self) -> bool {
+        self.user_id != 0
+    }
+}
+
+pub enum SessionAction {
+    Login(i64),
+    Logout,
+    /// Route was opened.
+    Navigate(Route),
+}
+
+impl Reducer<Session> for SessionAction {
+    fn apply(self, state: &mut Session) {
+        match self {
+            SessionAction::Login(user_id) => state.user_id = user_id,
+            SessionAction::Logout => *state = Session::default(),
+            SessionAction::Navigate(route) => {
+                state.is_settings_displayed = route.settings_page().is_some();
+                if let Route::Direct { id } = route {
+                    state.opened_channel = id;
+                } else if route.friends_tab().is_some() {
+                    state.opened_channel = 0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{cell::RefCell, collections::HashSet, num::NonZeroUsize, rc::Rc, sync::Arc};
+
+use lru::LruCache;
+
+use crate::account::load_user::{User, UserState, UserStatus};
+
+use super::{Reducer, Slice};
+
+const MAX_USERS: usize = 2048;
+
+/// Users loaded from the API, by their ids. Least recently loaded users are evicted. Clones of
+/// the slice share the cache, which is changed in place, so dispatching an action does not copy
+/// it.
+#[derive(Clone)]
+pub struct Users {
+    users: Rc<RefCell<LruCache<i64, Arc<User>>>>,
+    /// Users which do not exist or failed to load, until they are loaded.
+    missing: Rc<RefCell<HashSet<i64>>>,
+}
+
+impl Slice for Users {}
+
+impl Users {
+    /// Returns the user when it is loaded, and with status when it is required.
+    pub fn get(&self, user_id: i64, with_status: bool) -> Option<Arc<User>> {
+        self.users
+            .borrow()
+            .peek(&user_id)
+            .filter(|x| !with_status || x.status.is_some())
+            .cloned()
+    }
+
+    /// Returns the user, or whether it is still loading. User without status is returned when
+    /// it was loaded, but loading of the status failed.
+    pub fn state(&self, user_id: i64, with_status: bool) -> UserState {
+        if let Some(user) = self.get(user_id, with_status) {
+            return UserState::Loaded(user);
+        }
+        match self.missing.borrow().contains(&user_id) {
+            true => self
+                .get(user_id, false)
+                .map_or(UserState::Missing, UserState::Loaded),
+            false => UserState::Loading,
+        }
+    }
+}
+
+impl Default for Users {
+    fn default() -> Self {
+        Self {
+            users: Rc::new(RefCell::new(LruCache::new(
+                NonZeroUsize::new(MAX_USERS).unwrap(),
+            ))),
+            missing: Rc::new(RefCell::new(HashSet::new())),
+        }
+    }
+}
+
+pub enum UsersAction {
+    Loaded(Vec<User>),
+    /// Users which were requested, but not loaded.
+    Missing(Vec<i64>),
+    /// Status is changed only for users which are already loaded.
+    Status(i64, UserStatus),
+}
+
+impl Reducer<Users> for UsersAction {
+    fn apply(self, state: &mut Users) {
+        match self {
+            UsersAction::Loaded(users) => {
+                let mut cache = state.users.borrow_mut();
+                let mut missing = state.missing.borrow_mut();
+                for user in users {
+                    missing.remove(&user.user_id);
+                    cache.put(user.user_id, Arc::new(user));
+                }
+            }
+            UsersAction::Missing(user_ids) => state.missing.borrow_mut().extend(user_ids),
+            UsersAction::Status(user_id, status) => {
+                if let Some(user) = state.users.borrow_mut().get_mut(&user_id) {
+                    Arc::make_mut(user).status = Some(status);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    fn user(user_id: i64) -> User {
+        User {
+            user_id,
+            username: "harlequin".to_owned(),
+            name: "Harlequin".to_owned(),
+            avatar_url: String::new(),
+            status: None,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn missing_users_are_not_loading() {
+        let mut users = Users::default();
+        assert!(users.state(1, false) == UserState::Loading);
+
+        UsersAction::Missing(vec![1, 2]).apply(&mut users);
+        assert!(users.state(1, false) == UserState::Missing);
+
+        UsersAction::Loaded(vec![user(1)]).apply(&mut users);
+        assert!(matches!(users.state(1, false), UserState::Loaded(x) if x.user_id == 1));
+        // User without status is kept when loading of the status fails.
+        assert!(matches!(users.state(1, true), UserState::Loading));
+        UsersAction::Missing(vec![1]).apply(&mut users);
+        assert!(matches!(users.state(1, true), UserState::Loaded(_)));
+        assert!(users.state(2, true) == UserState::Missing);
+    }
+}
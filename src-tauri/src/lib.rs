@@ -29,6 +29,8 @@ impl AppBuilder {
     pub fn run(self) {
         let setup = self.setup;
         tauri::Builder::default()
+            // Provides the web Notification API to the frontend.
+            .plugin(tauri_plugin_notification::init())
             .setup(move |app| {
                 if let Some(setup) = setup {
                     (setup)(app)?;